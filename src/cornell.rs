@@ -0,0 +1,98 @@
+use std::env;
+use std::sync::Arc;
+
+use ray_tracing::materials::diffuse_light::DiffuseLight;
+use ray_tracing::materials::lambertian::Lambertian;
+use ray_tracing::materials::Material;
+use ray_tracing::objects::bvh::BvhNode;
+use ray_tracing::objects::camera::Camera;
+use ray_tracing::objects::cuboid::Cuboid;
+use ray_tracing::objects::instance::Instance;
+use ray_tracing::objects::rect::{XYRect, XZRect, YZRect};
+use ray_tracing::objects::*;
+use ray_tracing::output;
+use ray_tracing::render::integrator::{Background, NeePathTracer};
+use ray_tracing::render::tonemap::ToneMapper;
+use ray_tracing::render::{RenderSettings, Renderer};
+use ray_tracing::structs::transform::Transform;
+use ray_tracing::structs::vec3::Vec3;
+
+/// A box standing on the floor, `size` big, turned by `angle` degrees around
+/// its vertical axis through `center`.
+fn block(center: Vec3, size: Vec3, angle: f64, material: &Material) -> Instance {
+    let cuboid: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+        Vec3::new(-0.5 * size.x(), 0.0, -0.5 * size.z()),
+        Vec3::new(0.5 * size.x(), size.y(), 0.5 * size.z()),
+        material.clone(),
+    ));
+    Instance::new(
+        cuboid,
+        Transform::rotate_y(angle).then(&Transform::translate(center)),
+    )
+}
+
+/// The Cornell box and, separately, its light for next-event estimation.
+fn cornell_box() -> (HittableList, HittableList) {
+    let red = Material::Lambertian(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Material::Lambertian(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Material::Lambertian(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Material::DiffuseLight(DiffuseLight::new(Vec3::new(15.0, 15.0, 15.0)));
+
+    // The walls face into the box.
+    let mut list = HittableList::new();
+    list.push(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green).flip());
+    list.push(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red));
+    list.push(XZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, white.clone()));
+    list.push(XZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white.clone()).flip());
+    list.push(XYRect::new(0.0, 555.0, 0.0, 555.0, 555.0, white.clone()).flip());
+    // Slightly below the ceiling so that it is not hidden by it.
+    let mut lights = HittableList::new();
+    lights.push(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light).flip());
+
+    list.push(block(
+        Vec3::new(347.5, 0.0, 377.5),
+        Vec3::new(165.0, 330.0, 165.0),
+        15.0,
+        &white,
+    ));
+    list.push(block(
+        Vec3::new(212.5, 0.0, 147.5),
+        Vec3::new(165.0, 165.0, 165.0),
+        -18.0,
+        &white,
+    ));
+    for object in lights.objects() {
+        list.push_shared(Arc::clone(object));
+    }
+    (list, lights)
+}
+
+fn main() -> std::io::Result<()> {
+    let path: String = env::args().nth(1).unwrap_or_else(|| "img.png".to_string());
+
+    let image_size: usize = 400;
+    let samples_per_pixel: usize = 100;
+    let seed: u64 = 0;
+
+    let (objects, lights) = cornell_box();
+    let world = BvhNode::new(objects);
+    let camera = Camera::new(
+        Vec3::new(278.0, 278.0, -800.0),
+        Vec3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        10.0,
+    );
+
+    let mut settings = RenderSettings::new(image_size, image_size);
+    settings.samples_per_pixel = samples_per_pixel;
+    settings.seed = seed;
+    let integrator = NeePathTracer {
+        background: Background::Solid(Vec3::new(0.0, 0.0, 0.0)),
+        ..NeePathTracer::new(50, lights)
+    };
+    let image = Renderer::new(settings, integrator).render(&world, &camera);
+    output::save_tone_mapped(&path, &image, &ToneMapper::default())
+}
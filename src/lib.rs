@@ -1,3 +1,7 @@
+pub mod loaders;
 pub mod materials;
 pub mod objects;
+pub mod output;
+pub mod render;
 pub mod structs;
+pub mod textures;
@@ -0,0 +1,428 @@
+use std::fs;
+use std::path::Path;
+
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+use super::LoadError;
+use crate::output::png::{crc32, SIGNATURE};
+use crate::render::framebuffer::Framebuffer;
+use crate::render::tonemap::srgb_decode;
+use crate::structs::vec3::Vec3;
+
+/// Reads a PNG or PPM (`P2`, `P3`, `P5` or `P6`) image into linear RGB.
+///
+/// Both formats are taken to hold sRGB-encoded values. Alpha is dropped, and
+/// interlaced PNGs are not supported.
+pub fn load_image<P: AsRef<Path>>(path: P) -> Result<Framebuffer, LoadError> {
+    let path: &Path = path.as_ref();
+    let data: Vec<u8> = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+    if data.starts_with(SIGNATURE) {
+        decode_png(&data).map_err(|message| LoadError::invalid(path, message))
+    } else if data.len() >= 2 && data[0] == b'P' && b"2356".contains(&data[1]) {
+        decode_ppm(&data).map_err(|message| LoadError::invalid(path, message))
+    } else {
+        Err(LoadError::invalid(path, "not a PNG or PPM image"))
+    }
+}
+
+fn decode_ppm(data: &[u8]) -> Result<Framebuffer, String> {
+    let ascii: bool = data[1] == b'2' || data[1] == b'3';
+    let channels: usize = if data[1] == b'2' || data[1] == b'5' {
+        1
+    } else {
+        3
+    };
+
+    // Header tokens, skipping `#` comments.
+    let mut pos: usize = 2;
+    let mut header: Vec<usize> = Vec::new();
+    while header.len() < 3 {
+        match data.get(pos) {
+            None => return Err("truncated header".to_string()),
+            Some(b'#') => {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => pos += 1,
+            Some(_) => {
+                let start: usize = pos;
+                while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                let token: String = String::from_utf8_lossy(&data[start..pos]).into_owned();
+                let value: usize = token
+                    .parse()
+                    .map_err(|_| format!("invalid header value '{}'", token))?;
+                header.push(value);
+            }
+        }
+    }
+    let (width, height, max_value) = (header[0], header[1], header[2]);
+    if max_value == 0 || max_value > 65535 {
+        return Err(format!("invalid maximum value {}", max_value));
+    }
+    // Exactly one whitespace byte separates the header from binary data.
+    pos += 1;
+
+    let count: usize = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(channels))
+        .ok_or("image too large")?;
+    let samples: Vec<usize> = if ascii {
+        let text: String = String::from_utf8_lossy(data.get(pos..).unwrap_or(&[])).into_owned();
+        let samples: Vec<usize> = text
+            .split_whitespace()
+            .take(count)
+            .map(|t| t.parse().map_err(|_| format!("invalid sample '{}'", t)))
+            .collect::<Result<_, String>>()?;
+        samples
+    } else {
+        let bytes: usize = if max_value < 256 { 1 } else { 2 };
+        let body: &[u8] = data.get(pos..).unwrap_or(&[]);
+        body.chunks_exact(bytes)
+            .take(count)
+            .map(|b| {
+                if bytes == 1 {
+                    b[0] as usize
+                } else {
+                    ((b[0] as usize) << 8) | b[1] as usize
+                }
+            })
+            .collect()
+    };
+    if samples.len() < count {
+        return Err(format!(
+            "expected {} samples, found {}",
+            count,
+            samples.len()
+        ));
+    }
+    Ok(to_framebuffer(width, height, channels, &samples, max_value))
+}
+
+fn decode_png(data: &[u8]) -> Result<Framebuffer, String> {
+    let mut pos: usize = SIGNATURE.len();
+    let mut header: Option<(usize, usize, u8, u8)> = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut compressed: Vec<u8> = Vec::new();
+    loop {
+        if pos + 8 > data.len() {
+            return Err("missing IEND chunk".to_string());
+        }
+        let length: usize = be_u32(&data[pos..]) as usize;
+        let kind: &[u8] = &data[pos + 4..pos + 8];
+        if pos + 12 + length > data.len() {
+            return Err(format!("truncated {} chunk", String::from_utf8_lossy(kind)));
+        }
+        let body: &[u8] = &data[pos + 8..pos + 8 + length];
+        if be_u32(&data[pos + 8 + length..]) != crc32(crc32(0, kind), body) {
+            return Err(format!(
+                "bad CRC in {} chunk",
+                String::from_utf8_lossy(kind)
+            ));
+        }
+        match kind {
+            b"IHDR" => {
+                if length != 13 {
+                    return Err("invalid IHDR chunk".to_string());
+                }
+                if body[12] != 0 {
+                    return Err("interlaced PNGs are not supported".to_string());
+                }
+                let (bit_depth, color_type) = (body[8], body[9]);
+                let valid: bool = match color_type {
+                    0 => [1, 2, 4, 8, 16].contains(&bit_depth),
+                    3 => [1, 2, 4, 8].contains(&bit_depth),
+                    2 | 4 | 6 => [8, 16].contains(&bit_depth),
+                    _ => false,
+                };
+                if !valid {
+                    return Err(format!(
+                        "unsupported bit depth {} for color type {}",
+                        bit_depth, color_type
+                    ));
+                }
+                header = Some((
+                    be_u32(&body[0..]) as usize,
+                    be_u32(&body[4..]) as usize,
+                    bit_depth,
+                    color_type,
+                ));
+            }
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + length;
+    }
+
+    let (width, height, bit_depth, color_type) = header.ok_or("missing IHDR chunk")?;
+    let channels: usize = match color_type {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        _ => 4,
+    };
+    let bits_per_pixel: usize = channels * bit_depth as usize;
+    let row_bytes: usize = (width * bits_per_pixel).div_ceil(8);
+    let data_size: usize = (row_bytes + 1)
+        .checked_mul(height)
+        .ok_or("image too large")?;
+    let mut raw: Vec<u8> =
+        decompress_to_vec_zlib(&compressed).map_err(|_| "corrupt image data".to_string())?;
+    if raw.len() < data_size {
+        return Err("image data is too short".to_string());
+    }
+    unfilter(&mut raw, row_bytes, height, bits_per_pixel.div_ceil(8))?;
+
+    let max_value: usize = (1 << bit_depth) - 1;
+    let mut samples: Vec<usize> = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        let row: &[u8] = &raw[y * (row_bytes + 1) + 1..(y + 1) * (row_bytes + 1)];
+        for x in 0..width {
+            let sample = |c: usize| -> usize {
+                let index: usize = x * channels + c;
+                match bit_depth {
+                    16 => ((row[2 * index] as usize) << 8) | row[2 * index + 1] as usize,
+                    8 => row[index] as usize,
+                    bits => {
+                        let bit: usize = index * bits as usize;
+                        let shift: usize = 8 - bits as usize - bit % 8;
+                        (row[bit / 8] as usize >> shift) & max_value
+                    }
+                }
+            };
+            match color_type {
+                3 => {
+                    let entry: [u8; 3] = *palette
+                        .get(sample(0))
+                        .ok_or_else(|| format!("palette index {} out of range", sample(0)))?;
+                    samples.extend(entry.iter().map(|e| *e as usize));
+                }
+                0 | 4 => samples.extend([sample(0); 3].iter()),
+                _ => samples.extend([sample(0), sample(1), sample(2)].iter()),
+            }
+        }
+    }
+    // Palette entries are always 8-bit, whatever the index depth.
+    let max_value: usize = if color_type == 3 { 255 } else { max_value };
+    Ok(to_framebuffer(width, height, 3, &samples, max_value))
+}
+
+/// Reverses the per-scanline PNG filters in place. Each row in `raw` starts
+/// with its filter type byte.
+fn unfilter(raw: &mut [u8], row_bytes: usize, height: usize, bpp: usize) -> Result<(), String> {
+    let stride: usize = row_bytes + 1;
+    for y in 0..height {
+        let (before, rest) = raw.split_at_mut(y * stride);
+        let previous: Option<&[u8]> = if y == 0 {
+            None
+        } else {
+            Some(&before[(y - 1) * stride + 1..])
+        };
+        let filter: u8 = rest[0];
+        let row: &mut [u8] = &mut rest[1..stride];
+        for i in 0..row_bytes {
+            let a: u8 = if i >= bpp { row[i - bpp] } else { 0 };
+            let b: u8 = previous.map_or(0, |p| p[i]);
+            let c: u8 = if i >= bpp {
+                previous.map_or(0, |p| p[i - bpp])
+            } else {
+                0
+            };
+            let predictor: u8 = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("invalid filter type {} in row {}", filter, y)),
+            };
+            row[i] = row[i].wrapping_add(predictor);
+        }
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p: i16 = a as i16 + b as i16 - c as i16;
+    let pa: i16 = (p - a as i16).abs();
+    let pb: i16 = (p - b as i16).abs();
+    let pc: i16 = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn be_u32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+/// Converts sRGB samples in `0..=max_value`, one or three per pixel, to a
+/// linear image.
+fn to_framebuffer(
+    width: usize,
+    height: usize,
+    channels: usize,
+    samples: &[usize],
+    max_value: usize,
+) -> Framebuffer {
+    let mut image = Framebuffer::new(width, height);
+    let decode = |s: usize| -> f64 { srgb_decode(s as f64 / max_value as f64) };
+    for (i, pixel) in image.pixels.iter_mut().enumerate() {
+        *pixel = if channels == 1 {
+            let g: f64 = decode(samples[i]);
+            Vec3::new(g, g, g)
+        } else {
+            Vec3::new(
+                decode(samples[3 * i]),
+                decode(samples[3 * i + 1]),
+                decode(samples[3 * i + 2]),
+            )
+        };
+    }
+    image
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::png::write_png;
+    use crate::output::ppm::{write_p3, write_p6};
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+
+    fn image() -> Framebuffer {
+        let mut image = Framebuffer::new(3, 2);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            *pixel = Vec3::new(i as f64 / 5.0, 1.0 - i as f64 / 5.0, 0.5);
+        }
+        image
+    }
+
+    fn assert_close(a: &Framebuffer, b: &Framebuffer) {
+        assert_eq!((a.width, a.height), (b.width, b.height));
+        for (p, q) in a.pixels.iter().zip(b.pixels.iter()) {
+            // 8-bit sRGB steps are at most about 1% in linear terms.
+            assert!((*p - *q).length() < 0.01, "{:?} != {:?}", p, q);
+        }
+    }
+
+    fn chunk(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = (body.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out.extend_from_slice(&crc32(crc32(0, kind), body).to_be_bytes());
+        out
+    }
+
+    fn png(width: u32, height: u32, depth: u8, color: u8, extra: &[u8], rows: &[u8]) -> Vec<u8> {
+        let mut ihdr: Vec<u8> = width.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[depth, color, 0, 0, 0]);
+        let mut out: Vec<u8> = SIGNATURE.to_vec();
+        out.extend(chunk(b"IHDR", &ihdr));
+        out.extend_from_slice(extra);
+        out.extend(chunk(b"IDAT", &compress_to_vec_zlib(rows, 6)));
+        out.extend(chunk(b"IEND", &[]));
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_png(&mut bytes, &image()).unwrap();
+        assert_close(&decode_png(&bytes).unwrap(), &image());
+
+        let mut bytes: Vec<u8> = Vec::new();
+        write_p6(&mut bytes, &image()).unwrap();
+        assert_close(&decode_ppm(&bytes).unwrap(), &image());
+
+        let mut bytes: Vec<u8> = Vec::new();
+        write_p3(&mut bytes, &image()).unwrap();
+        assert_close(&decode_ppm(&bytes).unwrap(), &image());
+    }
+
+    #[test]
+    fn test_ppm_variants() {
+        let gray = decode_ppm(b"P2\n# comment\n2 1\n4\n0 4\n").unwrap();
+        assert_eq!(gray.get(0, 0), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(gray.get(1, 0), Vec3::new(1.0, 1.0, 1.0));
+        let wide = decode_ppm(b"P6 1 1 65535\n\xff\xff\x00\x00\xff\xff").unwrap();
+        assert_eq!(wide.get(0, 0), Vec3::new(1.0, 0.0, 1.0));
+        assert!(decode_ppm(b"P6 2 2 255\n\0\0\0").is_err());
+        assert_eq!(
+            decode_ppm(b"P6 4294967296 4294967296 255\n").unwrap_err(),
+            "image too large"
+        );
+    }
+
+    #[test]
+    fn test_png_filters() {
+        // One gray row per filter type, all decoding to 10 20 30.
+        let rows: Vec<u8> = vec![
+            0, 10, 20, 30, // None
+            1, 10, 10, 10, // Sub
+            2, 0, 0, 0, // Up
+            3, 5, 5, 5, // Average: (left + up) / 2
+            4, 0, 0, 0, // Paeth: up is closest to left + up - up-left
+        ];
+        let decoded = decode_png(&png(3, 5, 8, 0, &[], &rows)).unwrap();
+        for y in 0..5 {
+            for (x, value) in [10.0, 20.0, 30.0].iter().enumerate() {
+                let expected: f64 = srgb_decode(value / 255.0);
+                assert!(
+                    (decoded.get(x, y).x() - expected).abs() < 1e-12,
+                    "row {}",
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_png_palette_and_bit_depths() {
+        let palette = chunk(b"PLTE", &[255, 0, 0, 0, 0, 255]);
+        // Two-bit indices 1, 0, 1 packed into one byte.
+        let decoded = decode_png(&png(3, 1, 2, 3, &palette, &[0, 0b0100_0100])).unwrap();
+        assert_eq!(decoded.get(0, 0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(decoded.get(1, 0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(decoded.get(2, 0), Vec3::new(0.0, 0.0, 1.0));
+
+        // One-bit gray.
+        let decoded = decode_png(&png(3, 1, 1, 0, &[], &[0, 0b1010_0000])).unwrap();
+        assert_eq!(decoded.get(0, 0), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(decoded.get(1, 0), Vec3::new(0.0, 0.0, 0.0));
+
+        // 16-bit RGBA; alpha is dropped.
+        let rows: Vec<u8> = vec![0, 255, 255, 0, 0, 0, 0, 0, 0];
+        let decoded = decode_png(&png(1, 1, 16, 6, &[], &rows)).unwrap();
+        assert_eq!(decoded.get(0, 0), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_png_errors() {
+        let mut bytes = png(1, 1, 8, 0, &[], &[0, 0]);
+        let last: usize = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(decode_png(&bytes).unwrap_err().contains("CRC"));
+        assert!(decode_png(&png(1, 1, 8, 0, &[], &[9, 0]))
+            .unwrap_err()
+            .contains("filter"));
+        assert!(decode_png(&png(1, 1, 4, 2, &[], &[0, 0])).is_err());
+
+        let path =
+            std::env::temp_dir().join(format!("ray_tracing_image_{}.png", std::process::id()));
+        fs::write(&path, b"GIF89a").unwrap();
+        match load_image(&path) {
+            Err(LoadError::Invalid { message, .. }) => assert!(message.contains("not a PNG")),
+            _ => panic!("expected an error"),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub mod image;
+pub mod obj;
+pub mod ply;
+pub mod volume;
+
+/// Error raised while reading a scene or asset file.
+#[derive(Debug)]
+pub enum LoadError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// Malformed binary file, where line numbers mean nothing.
+    Invalid {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub fn io(path: &Path, error: io::Error) -> LoadError {
+        LoadError::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    pub fn parse<S: Into<String>>(path: &Path, line: usize, message: S) -> LoadError {
+        LoadError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid<S: Into<String>>(path: &Path, message: S) -> LoadError {
+        LoadError::Invalid {
+            path: path.to_path_buf(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            LoadError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            LoadError::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { error, .. } => Some(error),
+            LoadError::Parse { .. } | LoadError::Invalid { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::SplitWhitespace;
+use std::sync::Arc;
+
+use super::LoadError;
+use crate::materials::dielectric::Dielectric;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::metal::Metal;
+use crate::materials::Material;
+use crate::objects::mesh::TriangleMesh;
+use crate::objects::HittableList;
+use crate::structs::vec3::Vec3;
+
+/// Faces of one group that share a material.
+pub struct ObjMesh {
+    pub group: String,
+    pub material: String,
+    pub mesh: Arc<TriangleMesh>,
+}
+
+/// Geometry read from a Wavefront OBJ file, split by group and material.
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+}
+
+impl ObjModel {
+    /// All faces of all meshes, ready to be pushed into a world or a `BvhNode`.
+    pub fn to_hittable_list(&self) -> HittableList {
+        let mut list = HittableList::new();
+        for obj_mesh in self.meshes.iter() {
+            list.append(TriangleMesh::triangles(&obj_mesh.mesh));
+        }
+        list
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.meshes.iter().map(|m| m.mesh.len()).sum()
+    }
+}
+
+/// Material parameters of one `newmtl` block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MtlMaterial {
+    pub kd: Vec3,
+    pub ks: Vec3,
+    pub ns: f64,
+    pub ni: f64,
+    pub d: f64,
+    pub illum: u32,
+}
+
+impl MtlMaterial {
+    pub fn new() -> MtlMaterial {
+        MtlMaterial {
+            kd: Vec3::new(0.5, 0.5, 0.5),
+            ks: Vec3::new(0.0, 0.0, 0.0),
+            ns: 0.0,
+            ni: 1.5,
+            d: 1.0,
+            illum: 2,
+        }
+    }
+
+    /// Closest of the tracer's materials: transparent and refracting illumination
+    /// models become `Dielectric`, reflective ones `Metal` with fuzz derived from
+    /// the specular exponent, everything else `Lambertian`.
+    pub fn to_material(&self) -> Material {
+        match self.illum {
+            _ if self.d < 1.0 => Material::Dielectric(Dielectric::new(self.ni)),
+            4 | 6 | 7 | 9 => Material::Dielectric(Dielectric::new(self.ni)),
+            3 | 5 | 8 => {
+                let fuzz: f64 = (2.0 / (self.ns.max(0.0) + 2.0)).sqrt();
+                Material::Metal(Metal::new(self.ks, fuzz))
+            }
+            _ => Material::Lambertian(Lambertian::new(self.kd)),
+        }
+    }
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads an OBJ file and the MTL libraries it references.
+///
+/// Polygons are triangulated as fans, which is exact for the convex faces
+/// that modelling tools export. Faces that use a material missing from every
+/// library get the default `MtlMaterial`.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<ObjModel, LoadError> {
+    let path: &Path = path.as_ref();
+    let source: String = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut texcoords: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut builders: Vec<MeshBuilder> = Vec::new();
+    let mut builder_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut group = String::from("default");
+    let mut material = String::new();
+
+    for (n, line) in source.lines().enumerate() {
+        let line_no: usize = n + 1;
+        let mut tokens = Tokens::new(path, line_no, line);
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        match keyword {
+            "v" => positions.push(tokens.vec3()?),
+            "vn" => normals.push(tokens.vec3()?),
+            "vt" => {
+                let u: f64 = tokens.f64()?;
+                let v: f64 = tokens.optional_f64()?.unwrap_or(0.0);
+                texcoords.push((u, v));
+            }
+            "g" | "o" => {
+                let names: Vec<&str> = tokens.rest();
+                group = if names.is_empty() {
+                    String::from("default")
+                } else {
+                    names.join(" ")
+                };
+            }
+            "usemtl" => material = tokens.rest().join(" "),
+            "mtllib" => {
+                let dir: &Path = path.parent().unwrap_or_else(|| Path::new(""));
+                for file in tokens.rest() {
+                    materials.extend(load_mtl(dir.join(file))?);
+                }
+            }
+            "f" => {
+                let mut corners: Vec<Corner> = Vec::new();
+                for token in tokens.rest() {
+                    corners.push(parse_corner(
+                        path,
+                        line_no,
+                        token,
+                        positions.len(),
+                        texcoords.len(),
+                        normals.len(),
+                    )?);
+                }
+                if corners.len() < 3 {
+                    return Err(LoadError::parse(
+                        path,
+                        line_no,
+                        format!("face has {} vertices, expected at least 3", corners.len()),
+                    ));
+                }
+                let key = (group.clone(), material.clone());
+                let index: usize = *builder_index.entry(key).or_insert_with(|| {
+                    builders.push(MeshBuilder::new(&group, &material));
+                    builders.len() - 1
+                });
+                builders[index].push_polygon(&corners);
+            }
+            _ => {}
+        }
+    }
+
+    let meshes: Vec<ObjMesh> = builders
+        .into_iter()
+        .filter(|b| !b.indices.is_empty())
+        .map(|b| {
+            let mtl: MtlMaterial = materials.get(&b.material).copied().unwrap_or_default();
+            b.build(&positions, &texcoords, &normals, mtl.to_material())
+        })
+        .collect();
+    Ok(ObjModel { meshes })
+}
+
+/// Reads the materials of an MTL library by name.
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, MtlMaterial>, LoadError> {
+    let path: &Path = path.as_ref();
+    let source: String = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut current: Option<String> = None;
+    for (n, line) in source.lines().enumerate() {
+        let line_no: usize = n + 1;
+        let mut tokens = Tokens::new(path, line_no, line);
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            let name: String = tokens.rest().join(" ");
+            materials.insert(name.clone(), MtlMaterial::new());
+            current = Some(name);
+            continue;
+        }
+        let mtl: &mut MtlMaterial = match keyword {
+            "Kd" | "Ks" | "Ns" | "Ni" | "d" | "Tr" | "illum" => match current {
+                Some(ref name) => materials.get_mut(name).unwrap(),
+                None => {
+                    return Err(LoadError::parse(
+                        path,
+                        line_no,
+                        format!("'{}' before any 'newmtl'", keyword),
+                    ))
+                }
+            },
+            _ => continue,
+        };
+        match keyword {
+            "Kd" => mtl.kd = tokens.vec3()?,
+            "Ks" => mtl.ks = tokens.vec3()?,
+            "Ns" => mtl.ns = tokens.f64()?,
+            "Ni" => mtl.ni = tokens.f64()?,
+            "d" => mtl.d = tokens.f64()?,
+            "Tr" => mtl.d = 1.0 - tokens.f64()?,
+            "illum" => {
+                let value: f64 = tokens.f64()?;
+                if value < 0.0 || value.fract() != 0.0 {
+                    return Err(tokens.error(format!("invalid illumination model '{}'", value)));
+                }
+                mtl.illum = value as u32;
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(materials)
+}
+
+/// Whitespace-separated tokens of one line, with errors pointing at that line.
+struct Tokens<'a> {
+    path: &'a Path,
+    line: usize,
+    iter: SplitWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(path: &'a Path, line: usize, text: &'a str) -> Tokens<'a> {
+        let text: &str = match text.find('#') {
+            Some(i) => &text[..i],
+            None => text,
+        };
+        Tokens {
+            path,
+            line,
+            iter: text.split_whitespace(),
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.iter.next()
+    }
+
+    fn rest(&mut self) -> Vec<&'a str> {
+        self.iter.by_ref().collect()
+    }
+
+    fn error(&self, message: String) -> LoadError {
+        LoadError::parse(self.path, self.line, message)
+    }
+
+    fn optional_f64(&mut self) -> Result<Option<f64>, LoadError> {
+        match self.iter.next() {
+            Some(token) => match token.parse::<f64>() {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(self.error(format!("expected a number, found '{}'", token))),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn f64(&mut self) -> Result<f64, LoadError> {
+        match self.optional_f64()? {
+            Some(value) => Ok(value),
+            None => Err(self.error(String::from("expected a number, found end of line"))),
+        }
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, LoadError> {
+        Ok(Vec3::new(self.f64()?, self.f64()?, self.f64()?))
+    }
+}
+
+/// Position, texture coordinate and normal indices of one face corner, zero-based.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+fn parse_corner(
+    path: &Path,
+    line: usize,
+    token: &str,
+    position_count: usize,
+    texcoord_count: usize,
+    normal_count: usize,
+) -> Result<Corner, LoadError> {
+    let mut parts = token.split('/');
+    let resolve =
+        |part: Option<&str>, count: usize, what: &str| -> Result<Option<usize>, LoadError> {
+            let part: &str = match part {
+                Some(p) if !p.is_empty() => p,
+                _ => return Ok(None),
+            };
+            let index: i64 = part.parse().map_err(|_| {
+                LoadError::parse(path, line, format!("invalid {} index '{}'", what, part))
+            })?;
+            // OBJ indices are one-based; negative ones count back from the last element.
+            let resolved: i64 = if index < 0 {
+                count as i64 + index
+            } else {
+                index - 1
+            };
+            if index == 0 || resolved < 0 || resolved >= count as i64 {
+                return Err(LoadError::parse(
+                    path,
+                    line,
+                    format!("{} index {} out of range (have {})", what, index, count),
+                ));
+            }
+            Ok(Some(resolved as usize))
+        };
+    let position = match resolve(parts.next(), position_count, "vertex")? {
+        Some(p) => p,
+        None => {
+            return Err(LoadError::parse(
+                path,
+                line,
+                format!("face corner '{}' has no vertex index", token),
+            ))
+        }
+    };
+    let texcoord = resolve(parts.next(), texcoord_count, "texture coordinate")?;
+    let normal = resolve(parts.next(), normal_count, "normal")?;
+    Ok((position, texcoord, normal))
+}
+
+/// Collects the faces of one group/material pair and renumbers their vertices.
+struct MeshBuilder {
+    group: String,
+    material: String,
+    vertex_index: HashMap<Corner, usize>,
+    vertices: Vec<Corner>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl MeshBuilder {
+    fn new(group: &str, material: &str) -> MeshBuilder {
+        MeshBuilder {
+            group: group.to_string(),
+            material: material.to_string(),
+            vertex_index: HashMap::new(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    fn vertex(&mut self, corner: Corner) -> usize {
+        let vertices = &mut self.vertices;
+        *self.vertex_index.entry(corner).or_insert_with(|| {
+            vertices.push(corner);
+            vertices.len() - 1
+        })
+    }
+
+    fn push_polygon(&mut self, corners: &[Corner]) {
+        let first: usize = self.vertex(corners[0]);
+        for pair in corners[1..].windows(2) {
+            let b: usize = self.vertex(pair[0]);
+            let c: usize = self.vertex(pair[1]);
+            self.indices.push([first, b, c]);
+        }
+    }
+
+    /// Texture coordinates and normals are kept only if every vertex has them.
+    fn build(
+        self,
+        positions: &[Vec3],
+        texcoords: &[(f64, f64)],
+        normals: &[Vec3],
+        material: Material,
+    ) -> ObjMesh {
+        let mesh_positions: Vec<Vec3> = self.vertices.iter().map(|c| positions[c.0]).collect();
+        let mesh_uvs: Vec<(f64, f64)> = if self.vertices.iter().all(|c| c.1.is_some()) {
+            self.vertices
+                .iter()
+                .map(|c| texcoords[c.1.unwrap()])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mesh_normals: Vec<Vec3> = if self.vertices.iter().all(|c| c.2.is_some()) {
+            self.vertices
+                .iter()
+                .map(|c| normals[c.2.unwrap()])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        ObjMesh {
+            group: self.group,
+            material: self.material,
+            mesh: Arc::new(TriangleMesh::new(
+                mesh_positions,
+                mesh_normals,
+                mesh_uvs,
+                self.indices,
+                material,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::objects::{HitRecord, Hittable};
+    use crate::structs::ray::Ray;
+    use crate::textures::Texture;
+    use std::path::PathBuf;
+
+    fn write_temp(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ray_tracing_obj_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_obj() {
+        write_temp(
+            "materials.mtl",
+            "newmtl red\nKd 0.8 0.1 0.1\nillum 2\n\
+             newmtl mirror\nKs 0.9 0.9 0.9\nNs 1000\nillum 3\n\
+             newmtl glass\nNi 1.45\nd 0.1\n",
+        );
+        let path = write_temp(
+            "model.obj",
+            "# quad, pentagon and a triangle\n\
+             mtllib materials.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0.5 1.5 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+             vn 0 0 1\n\
+             g floor\nusemtl red\n\
+             f 1/1/1 2/2/1 3/3/1 4/4/1\n\
+             g roof\nusemtl mirror\n\
+             f 1 2 3 5 4\n\
+             usemtl glass\n\
+             f -3//1 -2//1 -1//1\n",
+        );
+        let model = load_obj(&path).unwrap();
+        assert_eq!(model.meshes.len(), 3);
+        assert_eq!(model.triangle_count(), 6);
+
+        let floor = &model.meshes[0];
+        assert_eq!(
+            (floor.group.as_str(), floor.material.as_str()),
+            ("floor", "red")
+        );
+        assert_eq!(floor.mesh.positions.len(), 4);
+        assert_eq!(floor.mesh.uvs.len(), 4);
+        assert_eq!(floor.mesh.normals.len(), 4);
+        match &floor.mesh.material {
+            Material::Lambertian(l) => {
+                assert_eq!(l.albedo, Texture::Solid(Vec3::new(0.8, 0.1, 0.1)))
+            }
+            _ => panic!("expected Lambertian"),
+        }
+
+        let roof = &model.meshes[1];
+        assert_eq!(roof.mesh.len(), 3);
+        assert!(roof.mesh.uvs.is_empty());
+        match &roof.mesh.material {
+            Material::Metal(m) => assert!(m.fuzz < 0.05),
+            _ => panic!("expected Metal"),
+        }
+
+        let glass = &model.meshes[2];
+        assert_eq!(glass.group, "roof");
+        assert_eq!(glass.mesh.indices, vec![[0, 1, 2]]);
+        assert_eq!(glass.mesh.positions[0], Vec3::new(1.0, 1.0, 0.0));
+        match glass.mesh.material {
+            Material::Dielectric(d) => assert_eq!(d.ref_idx, 1.45),
+            _ => panic!("expected Dielectric"),
+        }
+
+        let world = model.to_hittable_list();
+        let r = Ray::new(Vec3::new(0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let path = write_temp("bad_number.obj", "v 0 0 0\nv 1 x 0\n");
+        match load_obj(&path) {
+            Err(LoadError::Parse { line, message, .. }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("'x'"));
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        let path = write_temp("bad_index.obj", "v 0 0 0\nv 1 0 0\n\nf 1 2 3\n");
+        let error = load_obj(&path).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("bad_index.obj:4: vertex index 3 out of range (have 2)"));
+
+        let path = write_temp("missing_mtl.obj", "mtllib nowhere.mtl\n");
+        match load_obj(&path) {
+            Err(LoadError::Io { path, .. }) => assert!(path.ends_with("nowhere.mtl")),
+            _ => panic!("expected an io error"),
+        }
+    }
+}
@@ -0,0 +1,576 @@
+use std::fs;
+use std::path::Path;
+use std::str::SplitWhitespace;
+
+use super::LoadError;
+use crate::materials::Material;
+use crate::objects::mesh::TriangleMesh;
+use crate::structs::vec3::Vec3;
+
+/// Most items reserved up front for a list property. Longer lists still load,
+/// but a corrupt length cannot make the loader reserve memory it never fills.
+const MAX_LIST_RESERVE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Scalar::F32 | Scalar::F64)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Property {
+    Scalar(String, Scalar),
+    List(String, Scalar, Scalar),
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List(name, _, _) => name,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads a PLY mesh in ASCII or binary encoding.
+///
+/// Vertices need `x`, `y` and `z`; `nx`/`ny`/`nz` become shading normals,
+/// `u`/`v` (or `s`/`t`) texture coordinates and `red`/`green`/`blue` vertex
+/// colors, which replace the albedo of a `Lambertian` material. Integer colors
+/// are scaled from 0–255 to 0–1. Polygons are triangulated as fans and
+/// elements other than `vertex` and `face` are skipped.
+pub fn load_ply<P: AsRef<Path>>(path: P, material: Material) -> Result<TriangleMesh, LoadError> {
+    let path: &Path = path.as_ref();
+    let data: Vec<u8> = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+
+    let (format, elements, body_start, header_lines) = parse_header(path, &data)?;
+    let mut body: Body = match format {
+        Format::Ascii => {
+            let text: &str = std::str::from_utf8(&data[body_start..]).map_err(|_| {
+                LoadError::parse(path, header_lines + 1, "ASCII body is not valid UTF-8")
+            })?;
+            Body::Ascii(AsciiBody::new(text, header_lines))
+        }
+        _ => Body::Binary(BinaryBody {
+            data: &data[body_start..],
+            pos: 0,
+            big_endian: format == Format::BinaryBigEndian,
+            line: header_lines + 1,
+        }),
+    };
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut colors: Vec<Vec3> = Vec::new();
+    let mut indices: Vec<[usize; 3]> = Vec::new();
+
+    for element in elements.iter() {
+        let find = |names: &[&str]| -> Option<usize> {
+            element
+                .properties
+                .iter()
+                .position(|p| names.contains(&p.name()))
+        };
+        let xyz = (find(&["x"]), find(&["y"]), find(&["z"]));
+        let normal = (find(&["nx"]), find(&["ny"]), find(&["nz"]));
+        let uv = (
+            find(&["u", "s", "texture_u", "texture_s"]),
+            find(&["v", "t", "texture_v", "texture_t"]),
+        );
+        let rgb = (
+            find(&["red", "r", "diffuse_red"]),
+            find(&["green", "g", "diffuse_green"]),
+            find(&["blue", "b", "diffuse_blue"]),
+        );
+        let vertex_indices = find(&["vertex_indices", "vertex_index"]);
+        let color_scale: f64 = match rgb.0.map(|i| &element.properties[i]) {
+            Some(Property::Scalar(_, ty)) if ty.is_integer() => 1.0 / 255.0,
+            _ => 1.0,
+        };
+
+        let mut row: Vec<Value> = Vec::with_capacity(element.properties.len());
+        for item in 0..element.count {
+            row.clear();
+            for property in element.properties.iter() {
+                row.push(body.read_property(property).map_err(|message| {
+                    body.error(path, format!("{} #{}: {}", element.name, item, message))
+                })?);
+            }
+            let scalar = |i: usize| -> f64 {
+                match row[i] {
+                    Value::Scalar(v) => v,
+                    Value::List(_) => 0.0,
+                }
+            };
+
+            if element.name == "vertex" {
+                match xyz {
+                    (Some(x), Some(y), Some(z)) => {
+                        positions.push(Vec3::new(scalar(x), scalar(y), scalar(z)))
+                    }
+                    _ => {
+                        return Err(LoadError::parse(
+                            path,
+                            header_lines,
+                            "vertex element lacks x, y or z",
+                        ))
+                    }
+                }
+                if let (Some(x), Some(y), Some(z)) = normal {
+                    normals.push(Vec3::new(scalar(x), scalar(y), scalar(z)));
+                }
+                if let (Some(u), Some(v)) = uv {
+                    uvs.push((scalar(u), scalar(v)));
+                }
+                if let (Some(r), Some(g), Some(b)) = rgb {
+                    colors.push(Vec3::new(scalar(r), scalar(g), scalar(b)) * color_scale);
+                }
+            } else if element.name == "face" {
+                let polygon: &[f64] = match vertex_indices.map(|i| &row[i]) {
+                    Some(Value::List(list)) => list,
+                    _ => {
+                        return Err(LoadError::parse(
+                            path,
+                            header_lines,
+                            "face element lacks a vertex_indices list",
+                        ))
+                    }
+                };
+                let mut face: Vec<usize> = Vec::with_capacity(polygon.len());
+                for index in polygon.iter() {
+                    // Also rejects fractions and NaN, which `as usize` would truncate.
+                    if !(*index >= 0.0 && index.fract() == 0.0) {
+                        return Err(body.error(
+                            path,
+                            format!("face {} has invalid vertex index {}", item, index),
+                        ));
+                    }
+                    face.push(*index as usize);
+                }
+                if face.len() >= 3 {
+                    for pair in face[1..].windows(2) {
+                        indices.push([face[0], pair[0], pair[1]]);
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(bad) = indices.iter().flatten().find(|i| **i >= positions.len()) {
+        return Err(LoadError::parse(
+            path,
+            header_lines,
+            format!(
+                "face vertex index {} out of range (have {} vertices)",
+                bad,
+                positions.len()
+            ),
+        ));
+    }
+    Ok(TriangleMesh::new(positions, normals, uvs, indices, material).with_colors(colors))
+}
+
+/// Returns the format, the elements, the offset of the body and the number of header lines.
+fn parse_header(
+    path: &Path,
+    data: &[u8],
+) -> Result<(Format, Vec<Element>, usize, usize), LoadError> {
+    let mut format: Option<Format> = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos: usize = 0;
+    let mut line_no: usize = 0;
+
+    loop {
+        let end: usize = match data[pos..].iter().position(|b| *b == b'\n') {
+            Some(i) => pos + i,
+            None => return Err(LoadError::parse(path, line_no, "missing 'end_header'")),
+        };
+        line_no += 1;
+        let line: &str = std::str::from_utf8(&data[pos..end])
+            .map_err(|_| LoadError::parse(path, line_no, "header is not valid UTF-8"))?;
+        pos = end + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword: &str = tokens.next().unwrap_or("");
+
+        if line_no == 1 {
+            if keyword != "ply" {
+                return Err(LoadError::parse(path, line_no, "not a PLY file"));
+            }
+            continue;
+        }
+        let error = |message: String| LoadError::parse(path, line_no, message);
+        match keyword {
+            "format" => {
+                format = Some(match tokens.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    other => {
+                        return Err(error(format!("unknown format '{}'", other.unwrap_or(""))))
+                    }
+                });
+            }
+            "element" => {
+                let name: &str = tokens
+                    .next()
+                    .ok_or_else(|| error(String::from("element without a name")))?;
+                let count: usize = tokens
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .ok_or_else(|| error(format!("element '{}' without a valid count", name)))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            "property" => {
+                let parse_type = |name: Option<&str>| -> Result<Scalar, LoadError> {
+                    let name: &str = name.unwrap_or("");
+                    Scalar::parse(name)
+                        .ok_or_else(|| error(format!("unknown property type '{}'", name)))
+                };
+                let first: Option<&str> = tokens.next();
+                let property: Property = if first == Some("list") {
+                    let count_type: Scalar = parse_type(tokens.next())?;
+                    let item_type: Scalar = parse_type(tokens.next())?;
+                    let name: &str = tokens.next().unwrap_or("");
+                    Property::List(name.to_string(), count_type, item_type)
+                } else {
+                    let ty: Scalar = parse_type(first)?;
+                    let name: &str = tokens.next().unwrap_or("");
+                    Property::Scalar(name.to_string(), ty)
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => return Err(error(String::from("property before any element"))),
+                }
+            }
+            "end_header" => break,
+            "comment" | "obj_info" | "" => {}
+            other => return Err(error(format!("unknown header keyword '{}'", other))),
+        }
+    }
+
+    match format {
+        Some(format) => Ok((format, elements, pos, line_no)),
+        None => Err(LoadError::parse(path, line_no, "missing 'format' line")),
+    }
+}
+
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+enum Body<'a> {
+    Ascii(AsciiBody<'a>),
+    Binary(BinaryBody<'a>),
+}
+
+impl<'a> Body<'a> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        match self {
+            Body::Ascii(body) => body.read(),
+            Body::Binary(body) => body.read(ty),
+        }
+    }
+
+    fn read_property(&mut self, property: &Property) -> Result<Value, String> {
+        match property {
+            Property::Scalar(_, ty) => Ok(Value::Scalar(self.read(*ty)?)),
+            Property::List(_, count_type, item_type) => {
+                let count: f64 = self.read(*count_type)?;
+                if count < 0.0 || count.fract() != 0.0 {
+                    return Err(format!("invalid list length {}", count));
+                }
+                let mut list: Vec<f64> = Vec::with_capacity((count as usize).min(MAX_LIST_RESERVE));
+                for _ in 0..count as usize {
+                    list.push(self.read(*item_type)?);
+                }
+                Ok(Value::List(list))
+            }
+        }
+    }
+
+    /// Error at the current ASCII line, or at the first body line for binary data.
+    fn error(&self, path: &Path, message: String) -> LoadError {
+        match self {
+            Body::Ascii(body) => LoadError::parse(path, body.line, message),
+            Body::Binary(body) => LoadError::parse(
+                path,
+                body.line,
+                format!("{} (byte {} of the body)", message, body.pos),
+            ),
+        }
+    }
+}
+
+struct AsciiBody<'a> {
+    lines: std::str::Lines<'a>,
+    tokens: SplitWhitespace<'a>,
+    line: usize,
+}
+
+impl<'a> AsciiBody<'a> {
+    fn new(text: &'a str, header_lines: usize) -> AsciiBody<'a> {
+        AsciiBody {
+            lines: text.lines(),
+            tokens: "".split_whitespace(),
+            line: header_lines,
+        }
+    }
+
+    fn read(&mut self) -> Result<f64, String> {
+        loop {
+            if let Some(token) = self.tokens.next() {
+                return token
+                    .parse::<f64>()
+                    .map_err(|_| format!("expected a number, found '{}'", token));
+            }
+            match self.lines.next() {
+                Some(line) => {
+                    self.line += 1;
+                    self.tokens = line.split_whitespace();
+                }
+                None => return Err(String::from("unexpected end of file")),
+            }
+        }
+    }
+}
+
+struct BinaryBody<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+    /// Line on which the binary data starts, for error messages.
+    line: usize,
+}
+
+impl<'a> BinaryBody<'a> {
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        let size: usize = ty.size();
+        if self.pos + size > self.data.len() {
+            return Err(String::from("unexpected end of file"));
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.data[self.pos..self.pos + size]);
+        self.pos += size;
+        if self.big_endian {
+            bytes[..size].reverse();
+        }
+        Ok(match ty {
+            Scalar::I8 => bytes[0] as i8 as f64,
+            Scalar::U8 => bytes[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use std::path::PathBuf;
+
+    fn write_temp(name: &str, contents: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ray_tracing_ply_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn test_ascii() {
+        let path = write_temp(
+            "quad.ply",
+            b"ply\nformat ascii 1.0\ncomment unit quad\n\
+              element vertex 4\n\
+              property float x\nproperty float y\nproperty float z\n\
+              property uchar red\nproperty uchar green\nproperty uchar blue\n\
+              element face 1\nproperty list uchar int vertex_indices\n\
+              end_header\n\
+              0 0 0 255 0 0\n1 0 0 0 255 0\n1 1 0 0 0 255\n0 1 0 0 0 0\n\
+              4 0 1 2 3\n",
+        );
+        let mesh = load_ply(&path, material()).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.positions[2], Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.colors[1], Vec3::new(0.0, 1.0, 0.0));
+        assert!(mesh.normals.is_empty());
+    }
+
+    #[test]
+    fn test_binary() {
+        for big_endian in [false, true].iter() {
+            let format: &str = if *big_endian {
+                "binary_big_endian"
+            } else {
+                "binary_little_endian"
+            };
+            let mut data: Vec<u8> = format!(
+                "ply\nformat {} 1.0\n\
+                 element vertex 3\n\
+                 property float x\nproperty float y\nproperty float z\n\
+                 property float nx\nproperty float ny\nproperty float nz\n\
+                 element edge 1\nproperty list uchar uint pair\n\
+                 element face 1\nproperty list uchar int vertex_indices\n\
+                 end_header\n",
+                format
+            )
+            .into_bytes();
+            let f32_bytes = |v: f32| {
+                if *big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            };
+            let i32_bytes = |v: i32| {
+                if *big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            };
+            let vertices: [[f32; 6]; 3] = [
+                [0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+                [1.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+                [0.0, 2.0, 0.0, 0.0, 0.0, 1.0],
+            ];
+            for vertex in vertices.iter() {
+                for v in vertex.iter() {
+                    data.extend_from_slice(&f32_bytes(*v));
+                }
+            }
+            data.push(2);
+            data.extend_from_slice(&i32_bytes(0));
+            data.extend_from_slice(&i32_bytes(1));
+            data.push(3);
+            for i in 0..3 {
+                data.extend_from_slice(&i32_bytes(i));
+            }
+
+            let path = write_temp(&format!("{}.ply", format), &data);
+            let mesh = load_ply(&path, material()).unwrap();
+            assert_eq!(mesh.positions[2], Vec3::new(0.0, 2.0, 0.0));
+            assert_eq!(mesh.normals[0], Vec3::new(0.0, 0.0, 1.0));
+            assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let path = write_temp(
+            "bad_value.ply",
+            b"ply\nformat ascii 1.0\nelement vertex 2\n\
+              property float x\nproperty float y\nproperty float z\n\
+              end_header\n0 0 0\n1 oops 0\n",
+        );
+        let error = load_ply(&path, material()).err().unwrap();
+        match error {
+            LoadError::Parse {
+                line, ref message, ..
+            } => {
+                assert_eq!(line, 9);
+                assert!(message.contains("'oops'"));
+            }
+            _ => panic!("expected a parse error"),
+        }
+
+        let path = write_temp(
+            "truncated.ply",
+            b"ply\nformat binary_little_endian 1.0\nelement vertex 1\n\
+              property float x\nproperty float y\nproperty float z\n\
+              end_header\n\x00\x00",
+        );
+        let message: String = load_ply(&path, material()).err().unwrap().to_string();
+        assert!(message.contains("unexpected end of file"));
+
+        let path = write_temp(
+            "huge_list.ply",
+            b"ply\nformat binary_little_endian 1.0\nelement face 1\n\
+              property list uint int vertex_indices\n\
+              end_header\n\xff\xff\xff\xff\x00\x00\x00\x00",
+        );
+        let message: String = load_ply(&path, material()).err().unwrap().to_string();
+        assert!(message.contains("unexpected end of file"));
+
+        let path = write_temp(
+            "fractional_index.ply",
+            b"ply\nformat ascii 1.0\nelement vertex 3\n\
+              property float x\nproperty float y\nproperty float z\n\
+              element face 1\nproperty list uchar float vertex_indices\n\
+              end_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1.5 2\n",
+        );
+        let message: String = load_ply(&path, material()).err().unwrap().to_string();
+        assert!(message.contains("invalid vertex index 1.5"), "{}", message);
+
+        let path = write_temp(
+            "bad_type.ply",
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n",
+        );
+        let message: String = load_ply(&path, material()).err().unwrap().to_string();
+        assert!(message.ends_with("bad_type.ply:4: unknown property type 'quad'"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::Path;
+
+use super::LoadError;
+use crate::structs::aabb::Aabb;
+use crate::structs::vec3::Vec3;
+use crate::structs::voxel_grid::VoxelGrid;
+
+const MAGIC: &[u8] = b"VOL";
+const VERSION: u8 = 3;
+const HEADER_SIZE: usize = 48;
+
+/// Reads a dense voxel grid in Mitsuba's `.vol` format, which Blender and
+/// Mitsuba export smoke and fire caches to.
+///
+/// The file is a 48-byte little-endian header followed by the raw values:
+///
+/// | bytes | contents                                                  |
+/// |-------|-----------------------------------------------------------|
+/// | 0-3   | `VOL` and the version, 3                                  |
+/// | 4-7   | encoding: 1 for `f32` values, 3 for `u8` values in 0..255 |
+/// | 8-19  | resolution along x, y and z                               |
+/// | 20-23 | channels per sample                                       |
+/// | 24-47 | bounds as `f32` min x, y, z and max x, y, z               |
+///
+/// Values are stored with the channel varying fastest, then x, y and z. `u8`
+/// values are mapped to 0..1. OpenVDB files are not read; convert them to
+/// `.vol` first.
+pub fn load_volume<P: AsRef<Path>>(path: P) -> Result<VoxelGrid, LoadError> {
+    let path: &Path = path.as_ref();
+    let data: Vec<u8> = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+    decode_volume(&data).map_err(|message| LoadError::invalid(path, message))
+}
+
+fn decode_volume(data: &[u8]) -> Result<VoxelGrid, String> {
+    if data.len() < HEADER_SIZE || &data[0..3] != MAGIC {
+        return Err("not a .vol voxel grid".to_string());
+    }
+    if data[3] != VERSION {
+        return Err(format!("unsupported .vol version {}", data[3]));
+    }
+    let int = |offset: usize| -> i32 {
+        i32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let float = |offset: usize| -> f64 {
+        f64::from(f32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]))
+    };
+
+    let encoding: i32 = int(4);
+    let value_size: usize = match encoding {
+        1 => 4,
+        3 => 1,
+        _ => return Err(format!("unsupported .vol encoding {}", encoding)),
+    };
+    let mut resolution: [usize; 3] = [0; 3];
+    for (a, n) in resolution.iter_mut().enumerate() {
+        let value: i32 = int(8 + 4 * a);
+        if value <= 0 {
+            return Err(format!("invalid resolution {}", value));
+        }
+        *n = value as usize;
+    }
+    let channels: i32 = int(20);
+    if channels <= 0 {
+        return Err(format!("invalid channel count {}", channels));
+    }
+    let channels: usize = channels as usize;
+    let bounds = Aabb::new(
+        Vec3::new(float(24), float(28), float(32)),
+        Vec3::new(float(36), float(40), float(44)),
+    );
+
+    let count: usize = resolution
+        .iter()
+        .try_fold(channels, |count, &n| count.checked_mul(n))
+        .ok_or("grid too large")?;
+    let body: &[u8] = &data[HEADER_SIZE..];
+    if body.len() / value_size < count {
+        return Err(format!(
+            "expected {} values, found {}",
+            count,
+            body.len() / value_size
+        ));
+    }
+    let values: Vec<f32> = match encoding {
+        1 => body
+            .chunks_exact(4)
+            .take(count)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        _ => body
+            .iter()
+            .take(count)
+            .map(|&b| f32::from(b) / 255.0)
+            .collect(),
+    };
+    Ok(VoxelGrid::new(resolution, channels, bounds, values))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header(encoding: i32, resolution: [i32; 3], channels: i32) -> Vec<u8> {
+        let mut bytes: Vec<u8> = b"VOL\x03".to_vec();
+        for value in [
+            encoding,
+            resolution[0],
+            resolution[1],
+            resolution[2],
+            channels,
+        ]
+        .iter()
+        {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for bound in [-1.0f32, 0.0, 0.0, 1.0, 2.0, 0.5].iter() {
+            bytes.extend_from_slice(&bound.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut bytes: Vec<u8> = header(1, [2, 1, 1], 3);
+        for value in [0.5f32, 1.0, 1.5, 2.0, 2.5, 3.0].iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let grid = decode_volume(&bytes).unwrap();
+        assert_eq!(grid.resolution, [2, 1, 1]);
+        assert_eq!(grid.channels, 3);
+        assert_eq!(grid.bounds.min, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(grid.bounds.max, Vec3::new(1.0, 2.0, 0.5));
+        assert_eq!(grid.get(1, 0, 0, 2), 3.0);
+        assert_eq!(grid.value(&Vec3::new(0.0, 1.0, 0.25), 1), 1.75);
+
+        let mut bytes: Vec<u8> = header(3, [1, 1, 2], 1);
+        bytes.extend_from_slice(&[0, 255]);
+        let grid = decode_volume(&bytes).unwrap();
+        assert_eq!((grid.get(0, 0, 0, 0), grid.get(0, 0, 1, 0)), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode_volume(b"VOL").is_err());
+        let mut truncated: Vec<u8> = header(1, [2, 2, 2], 1);
+        truncated.extend_from_slice(&[0; 28]);
+        assert_eq!(
+            decode_volume(&truncated).unwrap_err(),
+            "expected 8 values, found 7"
+        );
+        assert!(decode_volume(&header(2, [1, 1, 1], 1)).is_err());
+        assert!(decode_volume(&header(1, [0, 1, 1], 1)).is_err());
+
+        let path = std::env::temp_dir().join("ray_tracing_missing.vol");
+        match load_volume(&path) {
+            Err(LoadError::Io { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
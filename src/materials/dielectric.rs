@@ -11,14 +11,14 @@ impl Dielectric {
     }
 
     pub fn refract(v: &Vec3, n: &Vec3, ni_over_nt: f64, refracted: &mut Vec3) -> bool {
-        let uv: Vec3 = Vec3::unit_vector(&v);
-        let dt: f64 = Vec3::dot(&uv, &n);
+        let uv: Vec3 = Vec3::unit_vector(v);
+        let dt: f64 = Vec3::dot(&uv, n);
         let discr: f64 = 1.0 - ni_over_nt * ni_over_nt * (1.0 - dt * dt);
         if discr > 0.0 {
             *refracted = (uv - *n * dt) * ni_over_nt - *n * discr.sqrt();
-            return true;
+            true
         } else {
-            return false;
+            false
         }
     }
 
@@ -29,48 +29,68 @@ impl Dielectric {
     }
 }
 
+/// Smooth glass: picks reflection or refraction by their Fresnel weight, so
+/// both are delta lobes with a weight of one.
 impl Scatterable for Dielectric {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
+    fn sample(&self, rec: &HitRecord, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let d: Vec3 = -*wo;
         let outward_normal: Vec3;
-        let reflected: Vec3 = Metal::reflect(&r_in.direction(), &rec.normal);
+        let reflected: Vec3 = Metal::reflect(&d, &rec.normal);
         let ni_over_nt: f64;
-        *attenuation = Vec3::new(1.0, 1.0, 1.0);
         let mut refracted: Vec3 = Vec3::new(0.0, 0.0, 0.0);
-        let reflect_prob: f64;
         let cosine: f64;
-        let mut rng = rand::thread_rng();
-        if Vec3::dot(&r_in.direction(), &rec.normal) > 0.0 {
+        if Vec3::dot(&d, &rec.normal) > 0.0 {
             outward_normal = -rec.normal;
             ni_over_nt = self.ref_idx;
-            cosine = self.ref_idx * Vec3::dot(&r_in.direction(), &rec.normal)
-                / r_in.direction().length();
+            cosine = self.ref_idx * Vec3::dot(&d, &rec.normal);
         } else {
             outward_normal = rec.normal;
             ni_over_nt = 1.0 / self.ref_idx;
-            cosine = -Vec3::dot(&r_in.direction(), &rec.normal) / r_in.direction().length();
+            cosine = -Vec3::dot(&d, &rec.normal);
         }
-        if Self::refract(
-            &r_in.direction(),
-            &outward_normal,
-            ni_over_nt,
-            &mut refracted,
-        ) {
-            reflect_prob = self.schlick(cosine);
+        let reflect_prob: f64 = if Self::refract(&d, &outward_normal, ni_over_nt, &mut refracted) {
+            self.schlick(cosine)
         } else {
-            *scattered = Ray::new(rec.p, reflected);
-            reflect_prob = 1.0;
-        }
-        if rng.gen::<f64>() < reflect_prob {
-            *scattered = Ray::new(rec.p, reflected);
+            1.0
+        };
+        let (direction, pdf): (Vec3, f64) = if sampler.gen::<f64>() < reflect_prob {
+            (reflected, reflect_prob)
         } else {
-            *scattered = Ray::new(rec.p, refracted);
+            (refracted, 1.0 - reflect_prob)
+        };
+        Some(BsdfSample {
+            direction: Vec3::unit_vector(&direction),
+            weight: Vec3::new(1.0, 1.0, 1.0),
+            pdf,
+            delta: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample_normal_incidence() {
+        let mut sampler = Sampler::new(0);
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3::new(0.0, 0.0, 1.0);
+        let glass = Dielectric::new(1.5);
+        let wo: Vec3 = rec.normal;
+        let mut reflections: usize = 0;
+        for _ in 0..1000 {
+            let sample = glass.sample(&rec, &wo, &mut sampler).unwrap();
+            assert!(sample.delta);
+            assert_eq!(sample.weight, Vec3::new(1.0, 1.0, 1.0));
+            if sample.direction.z() > 0.0 {
+                reflections += 1;
+                assert!((sample.pdf - 0.04).abs() < 1e-12);
+            } else {
+                assert!((sample.direction - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-12);
+                assert!((sample.pdf - 0.96).abs() < 1e-12);
+            }
         }
-        return true;
+        assert!(reflections > 20 && reflections < 60);
     }
 }
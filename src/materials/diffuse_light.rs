@@ -0,0 +1,24 @@
+use super::*;
+
+/// Emits `emit` equally in all directions from both sides of a surface and
+/// scatters nothing.
+#[derive(Clone, Copy, Debug)]
+pub struct DiffuseLight {
+    pub emit: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Vec3) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Scatterable for DiffuseLight {
+    fn sample(&self, _rec: &HitRecord, _wo: &Vec3, _sampler: &mut Sampler) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Vec3 {
+        self.emit
+    }
+}
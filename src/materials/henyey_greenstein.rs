@@ -0,0 +1,117 @@
+use super::*;
+use crate::textures::Texture;
+
+/// Henyey-Greenstein phase function. Positive `g` scatters light mostly
+/// onwards, as in haze and clouds, negative `g` mostly back; zero is
+/// isotropic. `g` is the mean cosine of the angle light is turned by.
+#[derive(Clone, Debug)]
+pub struct HenyeyGreenstein {
+    pub albedo: Texture,
+    pub g: f64,
+    /// Light added where a ray collides with the medium, as for `Isotropic`.
+    pub emission: Vec3,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(a: Vec3, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein::textured(Texture::Solid(a), g)
+    }
+
+    pub fn textured(albedo: Texture, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+            emission: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Density of turning light by an angle with cosine `cos_theta`.
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g: f64 = self.g;
+        let denom: f64 = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+}
+
+impl Scatterable for HenyeyGreenstein {
+    fn sample(&self, rec: &HitRecord, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let g: f64 = self.g;
+        let r1: f64 = sampler.gen::<f64>();
+        let r2: f64 = sampler.gen::<f64>();
+        let cos_theta: f64 = if g.abs() < 1e-3 {
+            1.0 - 2.0 * r1
+        } else {
+            let term: f64 = (1.0 - g * g) / (1.0 - g + 2.0 * g * r1);
+            ((1.0 + g * g - term * term) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+        let sin_theta: f64 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi: f64 = 2.0 * PI * r2;
+        // Light arriving from `wi` travels along `-wi`, so it is turned by
+        // theta when `wi` is theta away from `-wo`.
+        let direction: Vec3 = Onb::from_w(&-*wo).local(&Vec3::new(
+            phi.cos() * sin_theta,
+            phi.sin() * sin_theta,
+            cos_theta,
+        ));
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo.value(rec.u, rec.v, &rec.p),
+            pdf: self.phase(cos_theta),
+            delta: false,
+        })
+    }
+
+    fn eval(&self, rec: &HitRecord, wi: &Vec3, wo: &Vec3) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p) * self.pdf(rec, wi, wo)
+    }
+
+    fn pdf(&self, _rec: &HitRecord, wi: &Vec3, wo: &Vec3) -> f64 {
+        self.phase(-Vec3::dot(wi, wo))
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Vec3 {
+        self.emission
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample_matches_eval_and_pdf() {
+        let mut sampler = Sampler::new(0);
+        let rec = HitRecord::new();
+        let wo: Vec3 = Vec3::unit_vector(&Vec3::new(1.0, -2.0, 0.5));
+        for g in [-0.7, 0.0, 0.3, 0.9].iter() {
+            let phase = HenyeyGreenstein::new(Vec3::new(0.5, 0.6, 0.7), *g);
+            // The mean cosine of the scattering angle is g.
+            let n: usize = 50000;
+            let mut mean_cosine: f64 = 0.0;
+            for _ in 0..n {
+                let sample = phase.sample(&rec, &wo, &mut sampler).unwrap();
+                assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+                let pdf: f64 = phase.pdf(&rec, &sample.direction, &wo);
+                assert!((sample.pdf - pdf).abs() < 1e-9 * pdf.max(1.0));
+                let weight: Vec3 = phase.eval(&rec, &sample.direction, &wo) / sample.pdf;
+                assert!((weight - sample.weight).length() < 1e-9);
+                mean_cosine -= Vec3::dot(&sample.direction, &wo) / n as f64;
+            }
+            assert!((mean_cosine - g).abs() < 0.01, "{} {}", g, mean_cosine);
+        }
+    }
+
+    #[test]
+    fn test_normalized() {
+        // The phase function integrates to one over the sphere.
+        let phase = HenyeyGreenstein::new(Vec3::new(1.0, 1.0, 1.0), 0.6);
+        let n: usize = 100_000;
+        let integral: f64 = (0..n)
+            .map(|i| {
+                let cos_theta: f64 = -1.0 + 2.0 * (i as f64 + 0.5) / n as f64;
+                phase.phase(cos_theta) * 4.0 * PI / n as f64
+            })
+            .sum();
+        assert!((integral - 1.0).abs() < 1e-6, "{}", integral);
+    }
+}
@@ -0,0 +1,71 @@
+use super::*;
+use crate::textures::Texture;
+
+/// Phase function that scatters light equally in all directions, for the
+/// inside of a `ConstantMedium`.
+#[derive(Clone, Debug)]
+pub struct Isotropic {
+    pub albedo: Texture,
+    /// Light added where a ray collides with the medium, for glowing media.
+    /// `GridMedium` sets it at every collision from its emission grids.
+    pub emission: Vec3,
+}
+
+impl Isotropic {
+    pub fn new(a: Vec3) -> Isotropic {
+        Isotropic::textured(Texture::Solid(a))
+    }
+
+    pub fn textured(albedo: Texture) -> Isotropic {
+        Isotropic {
+            albedo,
+            emission: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Scatterable for Isotropic {
+    fn sample(&self, rec: &HitRecord, _wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        Some(BsdfSample {
+            direction: random_unit_vector(sampler),
+            weight: self.albedo.value(rec.u, rec.v, &rec.p),
+            pdf: 1.0 / (4.0 * PI),
+            delta: false,
+        })
+    }
+
+    fn eval(&self, rec: &HitRecord, _wi: &Vec3, _wo: &Vec3) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p) / (4.0 * PI)
+    }
+
+    fn pdf(&self, _rec: &HitRecord, _wi: &Vec3, _wo: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Vec3 {
+        self.emission
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let mut sampler = Sampler::new(0);
+        let rec = HitRecord::new();
+        let isotropic = Isotropic::new(Vec3::new(0.9, 0.8, 0.7));
+        let wo = Vec3::new(0.0, 0.0, 1.0);
+        let n: usize = 20000;
+        let mut mean = Vec3::new(0.0, 0.0, 0.0);
+        for _ in 0..n {
+            let sample = isotropic.sample(&rec, &wo, &mut sampler).unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            let weight: Vec3 = isotropic.eval(&rec, &sample.direction, &wo) / sample.pdf;
+            assert!((weight - sample.weight).length() < 1e-12);
+            mean += sample.direction / n as f64;
+        }
+        assert!(mean.length() < 0.02, "{:?}", mean);
+    }
+}
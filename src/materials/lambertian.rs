@@ -1,28 +1,40 @@
 use super::*;
+use crate::textures::Texture;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Lambertian {
-    pub albedo: Vec3,
+    pub albedo: Texture,
 }
 
 impl Lambertian {
     pub fn new(a: Vec3) -> Lambertian {
-        Lambertian { albedo: a }
+        Lambertian {
+            albedo: Texture::Solid(a),
+        }
+    }
+
+    pub fn textured(albedo: Texture) -> Lambertian {
+        Lambertian { albedo }
     }
 }
 
 impl Scatterable for Lambertian {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let target: Vec3 = rec.p + rec.normal + random_in_unit_sphere();
-        *scattered = Ray::new(rec.p, target - rec.p);
-        *attenuation = self.albedo;
-        true
+    fn sample(&self, rec: &HitRecord, _wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let direction: Vec3 = Onb::from_w(&rec.normal).local(&random_cosine_direction(sampler));
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo.value(rec.u, rec.v, &rec.p),
+            pdf: Vec3::dot(&direction, &rec.normal).max(0.0) / PI,
+            delta: false,
+        })
+    }
+
+    fn eval(&self, rec: &HitRecord, wi: &Vec3, _wo: &Vec3) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p) * (Vec3::dot(wi, &rec.normal).max(0.0) / PI)
+    }
+
+    fn pdf(&self, rec: &HitRecord, wi: &Vec3, _wo: &Vec3) -> f64 {
+        Vec3::dot(wi, &rec.normal).max(0.0) / PI
     }
 }
 
@@ -32,7 +44,53 @@ mod test {
 
     #[test]
     fn test_point() {
-        let vec: Vec3 = random_in_unit_sphere();
+        let mut sampler = Sampler::new(0);
+        let vec: Vec3 = random_in_unit_sphere(&mut sampler);
         assert_eq!(Vec3::dot(&vec, &Vec3::new(0.0, 0.0, 0.0)), 0.0);
     }
+
+    #[test]
+    fn test_sample_matches_eval_and_pdf() {
+        let mut sampler = Sampler::new(0);
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3::unit_vector(&Vec3::new(1.0, 2.0, -0.5));
+        let lambertian = Lambertian::new(Vec3::new(0.2, 0.5, 0.8));
+        let wo: Vec3 = rec.normal;
+        for _ in 0..100 {
+            let sample = lambertian.sample(&rec, &wo, &mut sampler).unwrap();
+            assert!(!sample.delta);
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            assert!((sample.pdf - lambertian.pdf(&rec, &sample.direction, &wo)).abs() < 1e-12);
+            let weight: Vec3 = lambertian.eval(&rec, &sample.direction, &wo) / sample.pdf;
+            assert!((weight - sample.weight).length() < 1e-9);
+        }
+        assert_eq!(lambertian.pdf(&rec, &-rec.normal, &wo), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_distribution() {
+        // E[cos] is 2/3 for a cosine-distributed direction.
+        let mut sampler = Sampler::new(0);
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3::new(0.0, 1.0, 0.0);
+        let lambertian = Lambertian::new(Vec3::new(0.5, 0.5, 0.5));
+        let r_in = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut attenuation = Vec3::new(0.0, 0.0, 0.0);
+        let mut scattered = r_in;
+        let n: usize = 20000;
+        let mut sum: f64 = 0.0;
+        for _ in 0..n {
+            assert!(lambertian.scatter(
+                &r_in,
+                &rec,
+                &mut attenuation,
+                &mut scattered,
+                &mut sampler
+            ));
+            let cosine: f64 = Vec3::unit_vector(&scattered.direction()).y();
+            assert!(cosine >= 0.0);
+            sum += cosine;
+        }
+        assert!((sum / n as f64 - 2.0 / 3.0).abs() < 0.01);
+    }
 }
@@ -1,23 +1,20 @@
 use super::*;
+use crate::textures::Texture;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Metal {
-    pub albedo: Vec3,
+    pub albedo: Texture,
     pub fuzz: f64,
 }
 
 impl Metal {
     pub fn new(a: Vec3, f: f64) -> Metal {
-        let fuzz: f64;
-        if f < 1.0 {
-            fuzz = f;
-        } else {
-            fuzz = 1.0;
-        }
-        Metal {
-            albedo: a,
-            fuzz: fuzz,
-        }
+        Metal::textured(Texture::Solid(a), f)
+    }
+
+    pub fn textured(albedo: Texture, f: f64) -> Metal {
+        let fuzz: f64 = if f < 1.0 { f } else { 1.0 };
+        Metal { albedo, fuzz }
     }
 
     pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {
@@ -25,18 +22,21 @@ impl Metal {
     }
 }
 
+/// Mirror reflection, blurred by `fuzz`. Even a fuzzed lobe is reported as a
+/// delta lobe: it has no closed-form density to evaluate.
 impl Scatterable for Metal {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
-        let reflected: Vec3 = Metal::reflect(&Vec3::unit_vector(&r_in.direction()), &rec.normal);
-        *scattered = Ray::new(rec.p, reflected + random_in_unit_sphere() * self.fuzz);
-        *attenuation = self.albedo;
-        Vec3::dot(&scattered.direction(), &rec.normal) > 0.0
+    fn sample(&self, rec: &HitRecord, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
+        let reflected: Vec3 = Metal::reflect(&-*wo, &rec.normal);
+        let direction: Vec3 = reflected + random_in_unit_sphere(sampler) * self.fuzz;
+        if Vec3::dot(&direction, &rec.normal) <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction: Vec3::unit_vector(&direction),
+            weight: self.albedo.value(rec.u, rec.v, &rec.p),
+            pdf: 1.0,
+            delta: true,
+        })
     }
 }
 
@@ -51,4 +51,24 @@ mod test {
 
         assert_eq!(Metal::reflect(&v, &n), v);
     }
+
+    #[test]
+    fn test_sample() {
+        let mut sampler = Sampler::new(0);
+        let mut rec = HitRecord::new();
+        rec.normal = Vec3::new(0.0, 1.0, 0.0);
+        let metal = Metal::new(Vec3::new(0.9, 0.8, 0.7), 0.0);
+        let wo: Vec3 = Vec3::unit_vector(&Vec3::new(1.0, 1.0, 0.0));
+        let sample = metal.sample(&rec, &wo, &mut sampler).unwrap();
+        assert!(sample.delta);
+        assert!(
+            (sample.direction - Vec3::unit_vector(&Vec3::new(-1.0, 1.0, 0.0))).length() < 1e-12
+        );
+        assert_eq!(sample.weight, Vec3::new(0.9, 0.8, 0.7));
+        assert_eq!(
+            metal.eval(&rec, &sample.direction, &wo),
+            Vec3::new(0.0, 0.0, 0.0)
+        );
+        assert_eq!(metal.pdf(&rec, &sample.direction, &wo), 0.0);
+    }
 }
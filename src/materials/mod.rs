@@ -1,22 +1,35 @@
+use std::f64::consts::PI;
+
 use rand::prelude::*;
 
 use crate::objects::HitRecord;
+use crate::structs::onb::Onb;
 use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
 use crate::structs::vec3::Vec3;
 
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod henyey_greenstein;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
 
 use self::dielectric::Dielectric;
+use self::diffuse_light::DiffuseLight;
+use self::henyey_greenstein::HenyeyGreenstein;
+use self::isotropic::Isotropic;
 use self::lambertian::Lambertian;
 use self::metal::Metal;
 
-pub fn random_in_unit_sphere() -> Vec3 {
+pub fn random_in_unit_sphere(sampler: &mut Sampler) -> Vec3 {
     let mut p: Vec3;
-    let mut rng = rand::thread_rng();
     loop {
-        p = Vec3::new(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()) * 2.0
+        p = Vec3::new(
+            sampler.gen::<f64>(),
+            sampler.gen::<f64>(),
+            sampler.gen::<f64>(),
+        ) * 2.0
             - Vec3::new(1.0, 1.0, 1.0);
         if p.length_squared() < 1.0 {
             break;
@@ -25,39 +38,140 @@ pub fn random_in_unit_sphere() -> Vec3 {
     p
 }
 
+/// Direction distributed uniformly over the whole unit sphere.
+pub fn random_unit_vector(sampler: &mut Sampler) -> Vec3 {
+    let z: f64 = 1.0 - 2.0 * sampler.gen::<f64>();
+    let phi: f64 = 2.0 * PI * sampler.gen::<f64>();
+    let r: f64 = (1.0 - z * z).max(0.0).sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, z)
+}
+
+/// Direction in the hemisphere around +z with density `cos(theta) / pi`.
+pub fn random_cosine_direction(sampler: &mut Sampler) -> Vec3 {
+    let r1: f64 = sampler.gen::<f64>();
+    let r2: f64 = sampler.gen::<f64>();
+    let phi: f64 = 2.0 * PI * r1;
+    let sin_theta: f64 = r2.sqrt();
+    Vec3::new(
+        phi.cos() * sin_theta,
+        phi.sin() * sin_theta,
+        (1.0 - r2).sqrt(),
+    )
+}
+
+/// Direction picked by `Scatterable::sample`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BsdfSample {
+    /// Direction the light arrives from, `wi`.
+    pub direction: Vec3,
+    /// `eval(wi, wo) / pdf`, what the path throughput is multiplied with.
+    pub weight: Vec3,
+    /// Solid-angle density of `direction`. For delta lobes this is the
+    /// probability of picking the lobe instead.
+    pub pdf: f64,
+    /// Whether the direction comes from a perfectly specular lobe, which
+    /// `eval` and `pdf` cannot reproduce.
+    pub delta: bool,
+}
+
+/// Scattering of light at a surface, or at a point inside a medium.
+///
+/// Directions are unit vectors pointing away from the hit point: `wo` towards
+/// the viewer, `wi` towards where the light comes from. Phase functions have
+/// no cosine term, so for them `eval` is the phase function times the albedo.
 pub trait Scatterable {
+    /// Picks an incoming direction for light leaving along `wo`, or `None` if
+    /// the surface absorbs the path.
+    fn sample(&self, rec: &HitRecord, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample>;
+
+    /// BSDF times the cosine of `wi` to the normal. Zero for delta lobes.
+    fn eval(&self, _rec: &HitRecord, _wi: &Vec3, _wo: &Vec3) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    /// Density with which `sample` picks `wi`. Zero for delta lobes.
+    fn pdf(&self, _rec: &HitRecord, _wi: &Vec3, _wo: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Radiance the surface emits back along `r_in`.
+    fn emitted(&self, _r_in: &Ray, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    /// Samples the ray `r_in` scatters into and its attenuation.
+    /// Returns `false` if the ray is absorbed.
     fn scatter(
         &self,
         r_in: &Ray,
         rec: &HitRecord,
         attenuation: &mut Vec3,
         scattered: &mut Ray,
-    ) -> bool;
+        sampler: &mut Sampler,
+    ) -> bool {
+        let wo: Vec3 = -Vec3::unit_vector(&r_in.direction());
+        match self.sample(rec, &wo, sampler) {
+            Some(sample) => {
+                *attenuation = sample.weight;
+                *scattered = Ray::with_time(rec.p, sample.direction, r_in.time());
+                true
+            }
+            None => false,
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub enum Material {
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
+    /// Phase functions, for the inside of participating media.
+    Isotropic(Isotropic),
+    HenyeyGreenstein(HenyeyGreenstein),
 }
 
 impl Scatterable for Material {
-    fn scatter(
-        &self,
-        r_in: &Ray,
-        rec: &HitRecord,
-        attenuation: &mut Vec3,
-        scattered: &mut Ray,
-    ) -> bool {
+    fn sample(&self, rec: &HitRecord, wo: &Vec3, sampler: &mut Sampler) -> Option<BsdfSample> {
         match self {
-            Material::Lambertian(ref material) => {
-                material.scatter(r_in, rec, attenuation, scattered)
-            }
-            Material::Metal(ref material) => material.scatter(r_in, rec, attenuation, scattered),
-            Material::Dielectric(ref material) => {
-                material.scatter(r_in, rec, attenuation, scattered)
-            }
+            Material::Lambertian(ref material) => material.sample(rec, wo, sampler),
+            Material::Metal(ref material) => material.sample(rec, wo, sampler),
+            Material::Dielectric(ref material) => material.sample(rec, wo, sampler),
+            Material::DiffuseLight(ref material) => material.sample(rec, wo, sampler),
+            Material::Isotropic(ref material) => material.sample(rec, wo, sampler),
+            Material::HenyeyGreenstein(ref material) => material.sample(rec, wo, sampler),
+        }
+    }
+
+    fn eval(&self, rec: &HitRecord, wi: &Vec3, wo: &Vec3) -> Vec3 {
+        match self {
+            Material::Lambertian(ref material) => material.eval(rec, wi, wo),
+            Material::Metal(ref material) => material.eval(rec, wi, wo),
+            Material::Dielectric(ref material) => material.eval(rec, wi, wo),
+            Material::DiffuseLight(ref material) => material.eval(rec, wi, wo),
+            Material::Isotropic(ref material) => material.eval(rec, wi, wo),
+            Material::HenyeyGreenstein(ref material) => material.eval(rec, wi, wo),
+        }
+    }
+
+    fn pdf(&self, rec: &HitRecord, wi: &Vec3, wo: &Vec3) -> f64 {
+        match self {
+            Material::Lambertian(ref material) => material.pdf(rec, wi, wo),
+            Material::Metal(ref material) => material.pdf(rec, wi, wo),
+            Material::Dielectric(ref material) => material.pdf(rec, wi, wo),
+            Material::DiffuseLight(ref material) => material.pdf(rec, wi, wo),
+            Material::Isotropic(ref material) => material.pdf(rec, wi, wo),
+            Material::HenyeyGreenstein(ref material) => material.pdf(rec, wi, wo),
+        }
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Vec3 {
+        match self {
+            Material::DiffuseLight(ref material) => material.emitted(r_in, rec),
+            Material::Isotropic(ref material) => material.emitted(r_in, rec),
+            Material::HenyeyGreenstein(ref material) => material.emitted(r_in, rec),
+            _ => Vec3::new(0.0, 0.0, 0.0),
         }
     }
 }
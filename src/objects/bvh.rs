@@ -0,0 +1,636 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use super::*;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+
+/// Relative cost of visiting an interior node, in units of one object intersection.
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+
+/// How the builder partitions the objects of a node between its two children.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitStrategy {
+    /// Split at the middle of the centroid bounds along the longest axis.
+    Midpoint,
+    /// Split into two halves with the same number of objects.
+    EqualCounts,
+    /// Binned surface area heuristic with the given number of buckets.
+    Sah { buckets: usize },
+}
+
+/// Configures and builds a `BvhNode`.
+#[derive(Clone, Copy, Debug)]
+pub struct BvhBuilder {
+    strategy: SplitStrategy,
+    max_leaf_size: usize,
+}
+
+impl BvhBuilder {
+    pub fn new() -> BvhBuilder {
+        BvhBuilder {
+            strategy: SplitStrategy::Sah { buckets: 12 },
+            max_leaf_size: 4,
+        }
+    }
+
+    pub fn strategy(mut self, strategy: SplitStrategy) -> BvhBuilder {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Nodes with at most this many objects become leaves.
+    /// With `Sah` larger nodes may become leaves too if splitting them does not pay off.
+    pub fn max_leaf_size(mut self, max_leaf_size: usize) -> BvhBuilder {
+        self.max_leaf_size = max_leaf_size.max(1);
+        self
+    }
+
+    /// Objects without a bounding box, such as planes, are kept out of the
+    /// hierarchy and tested for every ray.
+    pub fn build(&self, list: HittableList) -> BvhNode {
+        let mut primitives: Vec<Primitive> = Vec::new();
+        let mut unbounded: Vec<Arc<dyn Hittable>> = Vec::new();
+        for object in list.list.into_iter() {
+            let mut bbox = Aabb::empty();
+            if object.bounding_box(&mut bbox) {
+                primitives.push(Primitive {
+                    object,
+                    bbox,
+                    centroid: bbox.centroid(),
+                });
+            } else {
+                unbounded.push(object);
+            }
+        }
+        let mut root: BvhNode = if primitives.is_empty() {
+            BvhNode::leaf(Aabb::empty(), primitives)
+        } else {
+            self.build_node(primitives)
+        };
+        root.unbounded = unbounded;
+        root
+    }
+
+    fn build_node(&self, mut primitives: Vec<Primitive>) -> BvhNode {
+        let mut bbox = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for primitive in primitives.iter() {
+            bbox = Aabb::surrounding_box(&bbox, &primitive.bbox);
+            centroid_bounds = Aabb::surrounding_box(
+                &centroid_bounds,
+                &Aabb::new(primitive.centroid, primitive.centroid),
+            );
+        }
+        let axis: usize = centroid_bounds.longest_axis();
+        if primitives.len() == 1 || centroid_bounds.max[axis] == centroid_bounds.min[axis] {
+            return BvhNode::leaf(bbox, primitives);
+        }
+
+        let mid: usize = match self.strategy {
+            SplitStrategy::Midpoint => {
+                if primitives.len() <= self.max_leaf_size {
+                    return BvhNode::leaf(bbox, primitives);
+                }
+                let pivot: f64 = centroid_bounds.centroid()[axis];
+                let mid: usize = partition(&mut primitives, |p| p.centroid[axis] < pivot);
+                if mid == 0 || mid == primitives.len() {
+                    split_equal_counts(&mut primitives, axis)
+                } else {
+                    mid
+                }
+            }
+            SplitStrategy::EqualCounts => {
+                if primitives.len() <= self.max_leaf_size {
+                    return BvhNode::leaf(bbox, primitives);
+                }
+                split_equal_counts(&mut primitives, axis)
+            }
+            SplitStrategy::Sah { buckets } => {
+                match self.split_sah(&mut primitives, &bbox, &centroid_bounds, axis, buckets) {
+                    Some(mid) => mid,
+                    None => return BvhNode::leaf(bbox, primitives),
+                }
+            }
+        };
+
+        let right_primitives = primitives.split_off(mid);
+        let left = self.build_node(primitives);
+        let right = self.build_node(right_primitives);
+        BvhNode {
+            bbox,
+            node: Node::Interior {
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            unbounded: Vec::new(),
+        }
+    }
+
+    /// Partitions `primitives` at the cheapest bucket boundary, or returns `None`
+    /// if the node is small enough and a leaf is cheaper than any split.
+    fn split_sah(
+        &self,
+        primitives: &mut [Primitive],
+        bbox: &Aabb,
+        centroid_bounds: &Aabb,
+        axis: usize,
+        buckets: usize,
+    ) -> Option<usize> {
+        let buckets: usize = buckets.max(2);
+        let min: f64 = centroid_bounds.min[axis];
+        let extent: f64 = centroid_bounds.max[axis] - min;
+        let bucket_of = |p: &Primitive| -> usize {
+            (((p.centroid[axis] - min) / extent * buckets as f64) as usize).min(buckets - 1)
+        };
+
+        let mut counts: Vec<usize> = vec![0; buckets];
+        let mut bounds: Vec<Aabb> = vec![Aabb::empty(); buckets];
+        for primitive in primitives.iter() {
+            let b: usize = bucket_of(primitive);
+            counts[b] += 1;
+            bounds[b] = Aabb::surrounding_box(&bounds[b], &primitive.bbox);
+        }
+
+        // Sweep from the right once to get the area and count of every right side,
+        // then from the left to evaluate the cost of splitting after bucket `i`.
+        let mut right_area: Vec<f64> = vec![0.0; buckets];
+        let mut right_count: Vec<usize> = vec![0; buckets];
+        let mut right_box = Aabb::empty();
+        let mut count: usize = 0;
+        for i in (1..buckets).rev() {
+            right_box = Aabb::surrounding_box(&right_box, &bounds[i]);
+            count += counts[i];
+            right_area[i - 1] = right_box.surface_area();
+            right_count[i - 1] = count;
+        }
+
+        let mut best_cost: f64 = f64::INFINITY;
+        let mut best_bucket: usize = 0;
+        let mut left_box = Aabb::empty();
+        let mut left_count: usize = 0;
+        for i in 0..buckets - 1 {
+            left_box = Aabb::surrounding_box(&left_box, &bounds[i]);
+            left_count += counts[i];
+            if left_count == 0 || right_count[i] == 0 {
+                continue;
+            }
+            let cost: f64 = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (left_count as f64 * left_box.surface_area()
+                        + right_count[i] as f64 * right_area[i])
+                    / bbox.surface_area();
+            if cost < best_cost {
+                best_cost = cost;
+                best_bucket = i;
+            }
+        }
+
+        let leaf_cost: f64 = INTERSECTION_COST * primitives.len() as f64;
+        if primitives.len() <= self.max_leaf_size && leaf_cost <= best_cost {
+            return None;
+        }
+        if best_cost == f64::INFINITY {
+            return Some(split_equal_counts(primitives, axis));
+        }
+        Some(partition(primitives, |p| bucket_of(p) <= best_bucket))
+    }
+}
+
+impl Default for BvhBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Primitive {
+    object: Arc<dyn Hittable>,
+    bbox: Aabb,
+    centroid: Vec3,
+}
+
+/// Moves the elements matching `pred` to the front and returns how many there are.
+fn partition<F: Fn(&Primitive) -> bool>(primitives: &mut [Primitive], pred: F) -> usize {
+    let mut mid: usize = 0;
+    for i in 0..primitives.len() {
+        if pred(&primitives[i]) {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+fn split_equal_counts(primitives: &mut [Primitive], axis: usize) -> usize {
+    let mid: usize = primitives.len() / 2;
+    primitives.select_nth_unstable_by(mid, |a, b| {
+        a.centroid[axis]
+            .partial_cmp(&b.centroid[axis])
+            .unwrap_or(Ordering::Equal)
+    });
+    mid
+}
+
+enum Node {
+    Leaf(Vec<Arc<dyn Hittable>>),
+    Interior {
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+/// Bounding volume hierarchy over a set of hittables.
+///
+/// Returns the same closest hit as a linear `HittableList` over the same
+/// objects, but only tests the objects whose boxes the ray passes through.
+pub struct BvhNode {
+    bbox: Aabb,
+    node: Node,
+    /// Objects without bounds, only ever set on the root.
+    unbounded: Vec<Arc<dyn Hittable>>,
+}
+
+impl BvhNode {
+    /// Builds a hierarchy with the default `BvhBuilder` settings.
+    pub fn new(list: HittableList) -> BvhNode {
+        BvhBuilder::new().build(list)
+    }
+
+    fn leaf(bbox: Aabb, primitives: Vec<Primitive>) -> BvhNode {
+        BvhNode {
+            bbox,
+            node: Node::Leaf(primitives.into_iter().map(|p| p.object).collect()),
+            unbounded: Vec::new(),
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            unbounded_count: self.unbounded.len(),
+            ..BvhStats::default()
+        };
+        self.collect_stats(&mut stats, 1, self.bbox.surface_area());
+        stats
+    }
+
+    /// Closest of the hits that `hit_object`, either `hit` or
+    /// `sample_collision`, reports for the objects in the hierarchy.
+    fn closest_hit<'a, F>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        hit_object: &mut F,
+    ) -> bool
+    where
+        F: FnMut(&'a dyn Hittable, f64, f64, &mut HitRecord<'a>) -> bool,
+    {
+        let mut hit_unbounded = false;
+        let mut t_max = t_max;
+        for object in self.unbounded.iter() {
+            if hit_object(object.as_ref(), t_min, t_max, rec) {
+                hit_unbounded = true;
+                t_max = rec.t;
+            }
+        }
+        if !self.bbox.hit(r, t_min, t_max) {
+            return hit_unbounded;
+        }
+        let hit_tree: bool = match self.node {
+            Node::Leaf(ref objects) => {
+                let mut hit_anything = false;
+                let mut closest_so_far = t_max;
+                for object in objects.iter() {
+                    if hit_object(object.as_ref(), t_min, closest_so_far, rec) {
+                        hit_anything = true;
+                        closest_so_far = rec.t;
+                    }
+                }
+                hit_anything
+            }
+            Node::Interior {
+                ref left,
+                ref right,
+            } => {
+                let hit_left: bool = left.closest_hit(r, t_min, t_max, rec, hit_object);
+                let closest: f64 = if hit_left { rec.t } else { t_max };
+                let hit_right: bool = right.closest_hit(r, t_min, closest, rec, hit_object);
+                hit_left || hit_right
+            }
+        };
+        hit_tree || hit_unbounded
+    }
+
+    fn collect_stats(&self, stats: &mut BvhStats, depth: usize, root_area: f64) {
+        let relative_area: f64 = if root_area > 0.0 {
+            self.bbox.surface_area() / root_area
+        } else {
+            1.0
+        };
+        stats.node_count += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        match self.node {
+            Node::Leaf(ref objects) => {
+                stats.leaf_count += 1;
+                stats.object_count += objects.len();
+                if stats.leaf_size_histogram.len() <= objects.len() {
+                    stats.leaf_size_histogram.resize(objects.len() + 1, 0);
+                }
+                stats.leaf_size_histogram[objects.len()] += 1;
+                stats.sah_cost += INTERSECTION_COST * objects.len() as f64 * relative_area;
+            }
+            Node::Interior {
+                ref left,
+                ref right,
+            } => {
+                stats.interior_count += 1;
+                stats.sah_cost += TRAVERSAL_COST * relative_area;
+                left.collect_stats(stats, depth + 1, root_area);
+                right.collect_stats(stats, depth + 1, root_area);
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        self.closest_hit(r, t_min, t_max, rec, &mut |object, t_min, t_max, rec| {
+            object.hit(r, t_min, t_max, rec)
+        })
+    }
+
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        sampler: &mut Sampler,
+    ) -> bool {
+        self.closest_hit(r, t_min, t_max, rec, &mut |object, t_min, t_max, rec| {
+            object.sample_collision(r, t_min, t_max, rec, sampler)
+        })
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox;
+        self.unbounded.is_empty() && self.bbox != Aabb::empty()
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, sampler: &mut Sampler) -> f64 {
+        let mut transmittance: f64 = 1.0;
+        for object in self.unbounded.iter() {
+            transmittance *= object.transmittance(r, t_min, t_max, sampler);
+        }
+        if transmittance == 0.0 || !self.bbox.hit(r, t_min, t_max) {
+            return transmittance;
+        }
+        match self.node {
+            Node::Leaf(ref objects) => {
+                for object in objects.iter() {
+                    transmittance *= object.transmittance(r, t_min, t_max, sampler);
+                    if transmittance == 0.0 {
+                        break;
+                    }
+                }
+                transmittance
+            }
+            Node::Interior {
+                ref left,
+                ref right,
+            } => {
+                transmittance *= left.transmittance(r, t_min, t_max, sampler);
+                if transmittance == 0.0 {
+                    return 0.0;
+                }
+                transmittance * right.transmittance(r, t_min, t_max, sampler)
+            }
+        }
+    }
+}
+
+/// Shape of a built hierarchy, for comparing split strategies.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BvhStats {
+    pub node_count: usize,
+    pub interior_count: usize,
+    pub leaf_count: usize,
+    pub object_count: usize,
+    pub max_depth: usize,
+    /// `leaf_size_histogram[n]` is the number of leaves holding `n` objects.
+    pub leaf_size_histogram: Vec<usize>,
+    /// Expected cost of a random ray under the surface area heuristic.
+    pub sah_cost: f64,
+    /// Objects without bounds, tested outside the hierarchy.
+    pub unbounded_count: usize,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} nodes ({} interior, {} leaves), {} objects, max depth {}, SAH cost {:.3}",
+            self.node_count,
+            self.interior_count,
+            self.leaf_count,
+            self.object_count,
+            self.max_depth,
+            self.sah_cost
+        )?;
+        if self.unbounded_count > 0 {
+            writeln!(f, "{} unbounded objects", self.unbounded_count)?;
+        }
+        write!(f, "leaf sizes:")?;
+        for (size, count) in self.leaf_size_histogram.iter().enumerate() {
+            if *count > 0 {
+                write!(f, " {}x{}", count, size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::objects::plane::Plane;
+    use crate::objects::sphere::Sphere;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const STRATEGIES: [SplitStrategy; 4] = [
+        SplitStrategy::Midpoint,
+        SplitStrategy::EqualCounts,
+        SplitStrategy::Sah { buckets: 12 },
+        SplitStrategy::Sah { buckets: 2 },
+    ];
+
+    fn random_spheres(rng: &mut StdRng, n: usize) -> Vec<Sphere> {
+        let material = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let mut spheres: Vec<Sphere> = vec![Sphere::new(
+            Vec3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            material.clone(),
+        )];
+        for _ in 0..n {
+            let center = Vec3::new(
+                rng.gen_range(-11.0, 11.0),
+                rng.gen_range(0.0, 2.0),
+                rng.gen_range(-11.0, 11.0),
+            );
+            spheres.push(Sphere::new(
+                center,
+                rng.gen_range(0.05, 1.0),
+                material.clone(),
+            ));
+        }
+        spheres
+    }
+
+    fn to_list(spheres: &[Sphere]) -> HittableList {
+        let mut list = HittableList::new();
+        for sphere in spheres.iter() {
+            list.push(sphere.clone());
+        }
+        list
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = BvhNode::new(HittableList::new());
+        let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        let mut bbox = Aabb::empty();
+        assert!(!bvh.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!(!bvh.bounding_box(&mut bbox));
+    }
+
+    #[test]
+    fn test_same_hits_as_list() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for n in [1, 2, 3, 50, 500].iter() {
+            let spheres = random_spheres(&mut rng, *n);
+            let list = to_list(&spheres);
+            let bvhs: Vec<BvhNode> = STRATEGIES
+                .iter()
+                .map(|s| BvhBuilder::new().strategy(*s).build(to_list(&spheres)))
+                .collect();
+
+            for _ in 0..2000 {
+                let origin = Vec3::new(
+                    rng.gen_range(-15.0, 15.0),
+                    rng.gen_range(0.0, 10.0),
+                    rng.gen_range(-15.0, 15.0),
+                );
+                let direction = Vec3::new(
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                    rng.gen_range(-1.0, 1.0),
+                );
+                let r = Ray::new(origin, direction);
+                let mut list_rec = HitRecord::new();
+                let list_hit = list.hit(&r, 0.001, f64::MAX, &mut list_rec);
+                for bvh in bvhs.iter() {
+                    let mut bvh_rec = HitRecord::new();
+                    let bvh_hit = bvh.hit(&r, 0.001, f64::MAX, &mut bvh_rec);
+                    assert_eq!(list_hit, bvh_hit);
+                    if list_hit {
+                        assert_eq!(list_rec.t, bvh_rec.t);
+                        assert_eq!(list_rec.p, bvh_rec.p);
+                        assert_eq!(list_rec.normal, bvh_rec.normal);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let spheres = random_spheres(&mut rng, 300);
+        for strategy in STRATEGIES.iter() {
+            let stats = BvhBuilder::new()
+                .strategy(*strategy)
+                .max_leaf_size(2)
+                .build(to_list(&spheres))
+                .stats();
+            assert_eq!(stats.object_count, spheres.len());
+            assert_eq!(stats.node_count, stats.interior_count + stats.leaf_count);
+            assert_eq!(stats.leaf_count, stats.interior_count + 1);
+            assert_eq!(stats.leaf_count, stats.leaf_size_histogram.iter().sum());
+            assert!(stats.max_depth > 1);
+        }
+
+        let stats = BvhBuilder::new()
+            .strategy(SplitStrategy::EqualCounts)
+            .max_leaf_size(1)
+            .build(to_list(&spheres))
+            .stats();
+        assert_eq!(stats.leaf_size_histogram, vec![0, spheres.len()]);
+    }
+
+    #[test]
+    fn test_sah_isolates_ground() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let spheres = random_spheres(&mut rng, 300);
+        let sah = BvhBuilder::new()
+            .strategy(SplitStrategy::Sah { buckets: 12 })
+            .build(to_list(&spheres))
+            .stats();
+        let equal = BvhBuilder::new()
+            .strategy(SplitStrategy::EqualCounts)
+            .build(to_list(&spheres))
+            .stats();
+        assert!(sah.sah_cost < equal.sah_cost);
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let spheres = random_spheres(&mut rng, 50);
+        let ground = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let plane = Plane::new(Vec3::new(0.0, 0.1, 0.0), Vec3::new(0.0, 1.0, 0.0), ground);
+        let mut list = to_list(&spheres);
+        list.push(plane.clone());
+        let mut bvh_list = to_list(&spheres);
+        bvh_list.push(plane.clone());
+        let bvh = BvhNode::new(bvh_list);
+        assert_eq!(bvh.stats().unbounded_count, 1);
+        assert!(!bvh.bounding_box(&mut Aabb::empty()));
+
+        for _ in 0..2000 {
+            let origin = Vec3::new(
+                rng.gen_range(-15.0, 15.0),
+                rng.gen_range(0.0, 10.0),
+                rng.gen_range(-15.0, 15.0),
+            );
+            let direction = Vec3::new(
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            );
+            let r = Ray::new(origin, direction);
+            let mut list_rec = HitRecord::new();
+            let mut bvh_rec = HitRecord::new();
+            let list_hit = list.hit(&r, 0.001, f64::MAX, &mut list_rec);
+            assert_eq!(list_hit, bvh.hit(&r, 0.001, f64::MAX, &mut bvh_rec));
+            if list_hit {
+                assert_eq!(list_rec.t, bvh_rec.t);
+            }
+        }
+
+        // Only unbounded objects.
+        let mut only_plane = HittableList::new();
+        only_plane.push(plane);
+        let bvh = BvhNode::new(only_plane);
+        let down = Ray::new(Vec3::new(0.0, 1.1, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut rec = HitRecord::new();
+        assert!(bvh.hit(&down, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 1.0).abs() < 1e-12);
+    }
+}
@@ -2,6 +2,7 @@ use rand::prelude::*;
 use std::f64;
 
 use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
 use crate::structs::vec3::Vec3;
 
 pub struct Camera {
@@ -13,14 +14,16 @@ pub struct Camera {
     pub u: Vec3,
     pub v: Vec3,
     pub w: Vec3,
+    /// Shutter interval; rays get times spread uniformly over `[time0, time1]`.
+    pub time0: f64,
+    pub time1: f64,
 }
 
-fn random_in_unit_disk() -> Vec3 {
-    let p: Vec3 = Vec3::new(2.0, 2.0, 2.0);
-    let mut rng = rand::thread_rng();
+fn random_in_unit_disk(sampler: &mut Sampler) -> Vec3 {
+    let mut p: Vec3;
     loop {
-        let p: Vec3 =
-            Vec3::new(rng.gen::<f64>(), rng.gen::<f64>(), 0.0) * 2.0 - Vec3::new(1.0, 1.0, 0.0);
+        p = Vec3::new(sampler.gen::<f64>(), sampler.gen::<f64>(), 0.0) * 2.0
+            - Vec3::new(1.0, 1.0, 0.0);
         if Vec3::dot(&p, &p) < 1.0 {
             break;
         }
@@ -53,19 +56,35 @@ impl Camera {
             horizontal: u * half_width * focus_dist * 2.0,
             vertical: v * half_height * focus_dist * 2.0,
             lens_radius: apeture / 2.0,
-            u: u,
-            v: v,
-            w: w,
+            u,
+            v,
+            w,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd: Vec3 = random_in_unit_disk() * self.lens_radius;
+
+    /// Keeps the shutter open from `time0` to `time1`, blurring objects that
+    /// move in between.
+    pub fn shutter(mut self, time0: f64, time1: f64) -> Camera {
+        assert!(time0 <= time1, "shutter closes before it opens");
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
+    pub fn get_ray(&self, s: f64, t: f64, sampler: &mut Sampler) -> Ray {
+        let rd: Vec3 = random_in_unit_disk(sampler) * self.lens_radius;
         let offset: Vec3 = self.u * rd.x() + self.v * rd.y();
-        Ray {
-            a: self.origin + offset,
-            b: self.lower_left_corner + self.horizontal * s + self.vertical * t
-                - self.origin
-                - offset,
-        }
+        let time: f64 = if self.time1 > self.time0 {
+            self.time0 + (self.time1 - self.time0) * sampler.gen::<f64>()
+        } else {
+            self.time0
+        };
+        Ray::with_time(
+            self.origin + offset,
+            self.lower_left_corner + self.horizontal * s + self.vertical * t - self.origin - offset,
+            time,
+        )
     }
 }
@@ -0,0 +1,161 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use super::disk::Disk;
+use super::quadric::{phi, solve_quadratic, sweep};
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Cone around the y axis with its base of radius `radius` at y = 0 and its
+/// apex at y = `height`, open at the base unless capped. The side normal faces
+/// away from the axis.
+///
+/// `u` runs from 0 to 1 around the sweep and `v` from 0 at the base to 1 at
+/// the apex.
+#[derive(Clone)]
+pub struct Cone {
+    pub radius: f64,
+    pub height: f64,
+    /// Sweep in radians.
+    pub phi_max: f64,
+    pub material: Material,
+    base: Option<Disk>,
+}
+
+impl Cone {
+    pub fn new(radius: f64, height: f64, material: Material) -> Cone {
+        Cone {
+            radius,
+            height,
+            phi_max: 2.0 * PI,
+            material,
+            base: None,
+        }
+    }
+
+    /// Cuts the cone down to a sector of `degrees`, base included.
+    pub fn phi_max(mut self, degrees: f64) -> Cone {
+        self.phi_max = sweep(degrees);
+        self.base = self.base.map(|base| base.phi_max(degrees));
+        self
+    }
+
+    /// Closes the base with a disk of the same material.
+    pub fn capped(mut self) -> Cone {
+        let base = Disk::new(0.0, self.radius, self.material.clone())
+            .phi_max(self.phi_max.to_degrees())
+            .flip();
+        self.base = Some(base);
+        self
+    }
+
+    fn hit_side<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (o, d) = (r.origin(), r.direction());
+        // x^2 + z^2 = k (h - y)^2, with the apex at y = h.
+        let k: f64 = (self.radius / self.height) * (self.radius / self.height);
+        let apex_offset: f64 = self.height - o.y();
+        let a: f64 = d.x() * d.x() + d.z() * d.z() - k * d.y() * d.y();
+        let b: f64 = 2.0 * (o.x() * d.x() + o.z() * d.z() + k * apex_offset * d.y());
+        let c: f64 = o.x() * o.x() + o.z() * o.z() - k * apex_offset * apex_offset;
+        let (t0, t1) = match solve_quadratic(a, b, c) {
+            Some(roots) => roots,
+            None => return false,
+        };
+        for t in [t0, t1].iter() {
+            if *t <= t_min || *t >= t_max {
+                continue;
+            }
+            let p: Vec3 = r.point_at_parameter(*t);
+            let phi: f64 = phi(&p);
+            // The equation also describes a mirrored cone above the apex.
+            if p.y() < 0.0 || p.y() > self.height || phi > self.phi_max {
+                continue;
+            }
+            rec.t = *t;
+            rec.p = p;
+            rec.normal = Vec3::unit_vector(&Vec3::new(p.x(), k * (self.height - p.y()), p.z()));
+            rec.geometric_normal = rec.normal;
+            rec.u = phi / self.phi_max;
+            rec.v = p.y() / self.height;
+            rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+            rec.material = Cow::Borrowed(&self.material);
+            return true;
+        }
+        false
+    }
+}
+
+impl Hittable for Cone {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let hit_side: bool = self.hit_side(r, t_min, t_max, rec);
+        let closest: f64 = if hit_side { rec.t } else { t_max };
+        let hit_base: bool = match &self.base {
+            Some(base) => base.hit(r, t_min, closest, rec),
+            None => false,
+        };
+        hit_side || hit_base
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Vec3::new(-self.radius, 0.0, -self.radius),
+            Vec3::new(self.radius, self.height, self.radius),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn cone() -> Cone {
+        Cone::new(
+            1.0,
+            2.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let cone = cone();
+        let mut rec = HitRecord::new();
+        // Halfway up the radius is 0.5.
+        let r = Ray::new(Vec3::new(5.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(cone.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-12);
+        assert!((rec.v - 0.5).abs() < 1e-12);
+        // The slope is 1 in 2, so the normal leans up by the same ratio.
+        let expected = Vec3::unit_vector(&Vec3::new(2.0, 1.0, 0.0));
+        assert!((rec.normal - expected).length() < 1e-12);
+
+        // Above the apex only the mirrored nappe lies on the ray.
+        let r = Ray::new(Vec3::new(5.0, 3.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!cone.hit(&r, 0.0, f64::MAX, &mut rec));
+
+        let up = Ray::new(Vec3::new(0.25, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(cone.hit(&up, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 2.5).abs() < 1e-12);
+        let capped = cone.clone().capped();
+        assert!(capped.hit(&up, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn test_phi_max() {
+        let quarter = cone().phi_max(90.0);
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(0.25, 1.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(quarter.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.p.z() + 0.1875f64.sqrt()).abs() < 1e-12);
+        assert!(!quarter.hit(&r, 0.0, 4.5, &mut rec));
+        let r = Ray::new(Vec3::new(-0.25, 1.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!quarter.hit(&r, 0.0, f64::MAX, &mut rec));
+    }
+}
@@ -0,0 +1,210 @@
+use std::borrow::Cow;
+use std::f64;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Homogeneous participating medium, such as smoke or haze, filling the inside
+/// of a closed `boundary`, or all of space for a global fog.
+///
+/// A ray travelling through it is "hit" at a free-flight distance sampled
+/// from the exponential distribution with rate `density`, and scatters there
+/// according to `phase_function`, usually `Material::Isotropic` or
+/// `Material::HenyeyGreenstein`. Rays that get through without a hit see what
+/// lies behind, so transmittance comes out right on average. Only
+/// `sample_collision` can stop a ray; `hit` never reports the medium.
+#[derive(Clone)]
+pub struct ConstantMedium {
+    pub boundary: Option<Arc<dyn Hittable>>,
+    pub density: f64,
+    pub phase_function: Material,
+}
+
+impl ConstantMedium {
+    /// Medium inside the convex shape `boundary`.
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        phase_function: Material,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary: Some(boundary),
+            density,
+            phase_function,
+        }
+    }
+
+    /// Fog filling the whole scene. It has no bounding box, so a `BvhNode`
+    /// keeps it outside the hierarchy. No ray ever leaves it, so the
+    /// background is never seen and the scene has to be lit by emitters.
+    pub fn global(density: f64, phase_function: Material) -> ConstantMedium {
+        ConstantMedium {
+            boundary: None,
+            density,
+            phase_function,
+        }
+    }
+
+    /// Parameter range where `r` is inside the boundary, within `t_min` and `t_max`.
+    fn inside(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let (enter, exit): (f64, f64) = match &self.boundary {
+            Some(boundary) => {
+                // The entry may lie behind the origin when the ray starts inside.
+                let mut rec = HitRecord::new();
+                if !boundary.hit(r, f64::NEG_INFINITY, f64::INFINITY, &mut rec) {
+                    return None;
+                }
+                let enter: f64 = rec.t;
+                if !boundary.hit(r, enter + 0.0001, f64::INFINITY, &mut rec) {
+                    return None;
+                }
+                (enter, rec.t)
+            }
+            None => (t_min, t_max),
+        };
+        let enter: f64 = enter.max(t_min);
+        let exit: f64 = exit.min(t_max);
+        if enter < exit {
+            Some((enter, exit))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit<'a>(&'a self, _r: &Ray, _t_min: f64, _t_max: f64, _rec: &mut HitRecord<'a>) -> bool {
+        false
+    }
+
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let (enter, exit) = match self.inside(r, t_min, t_max) {
+            Some(range) => range,
+            None => return false,
+        };
+        let length: f64 = r.direction().length();
+        let hit_distance: f64 = -(1.0 - sampler.gen::<f64>()).ln() / self.density;
+        if hit_distance >= (exit - enter) * length {
+            return false;
+        }
+        rec.t = enter + hit_distance / length;
+        rec.p = r.point_at_parameter(rec.t);
+        // Phase functions ignore the normal; this one faces back along the ray.
+        rec.normal = -Vec3::unit_vector(&r.direction());
+        rec.geometric_normal = rec.normal;
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.phase_function);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        match &self.boundary {
+            Some(boundary) => boundary.bounding_box(output_box),
+            None => false,
+        }
+    }
+
+    /// Exact, `exp(-density * distance)` over the part of the ray inside.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, _sampler: &mut Sampler) -> f64 {
+        match self.inside(r, t_min, t_max) {
+            Some((enter, exit)) => (-self.density * (exit - enter) * r.direction().length()).exp(),
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::isotropic::Isotropic;
+    use crate::objects::cuboid::Cuboid;
+
+    fn phase() -> Material {
+        Material::Isotropic(Isotropic::new(Vec3::new(0.8, 0.8, 0.8)))
+    }
+
+    #[test]
+    fn test_transmittance() {
+        // A unit cube of density 1.5 lets exp(-1.5) of the rays through.
+        let cube: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            phase(),
+        ));
+        let medium = ConstantMedium::new(cube, 1.5, phase());
+        let mut rec = HitRecord::new();
+        let mut sampler = Sampler::new(0);
+        let n: usize = 20000;
+        let mut passed: usize = 0;
+        let r = Ray::new(Vec3::new(0.5, 0.5, -2.0), Vec3::new(0.0, 0.0, 2.0));
+        for _ in 0..n {
+            if medium.sample_collision(&r, 0.001, f64::MAX, &mut rec, &mut sampler) {
+                assert!(rec.p.z() >= 0.0 && rec.p.z() < 1.0);
+                assert!(rec.t > 1.0 && rec.t < 1.5);
+                assert!(matches!(*rec.material, Material::Isotropic(_)));
+            } else {
+                passed += 1;
+            }
+        }
+        let transmittance: f64 = passed as f64 / n as f64;
+        assert!(
+            (transmittance - (-1.5f64).exp()).abs() < 0.01,
+            "{}",
+            transmittance
+        );
+
+        // Rays starting inside collide before they leave. The medium has no
+        // surface, so `hit` never reports it.
+        let inside = Ray::new(Vec3::new(0.5, 0.5, 0.5), Vec3::new(0.0, 1.0, 0.0));
+        for _ in 0..100 {
+            if medium.sample_collision(&inside, 0.001, f64::MAX, &mut rec, &mut sampler) {
+                assert!(rec.t < 0.5);
+            }
+        }
+        assert!(!medium.hit(&r, 0.001, f64::MAX, &mut rec));
+        let miss = Ray::new(Vec3::new(2.0, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!medium.sample_collision(&miss, 0.001, f64::MAX, &mut rec, &mut sampler));
+        let mut bbox = Aabb::empty();
+        assert!(medium.bounding_box(&mut bbox));
+        assert_eq!(bbox.max, Vec3::new(1.0, 1.0, 1.0));
+
+        let transmittance: f64 = medium.transmittance(&r, 0.001, f64::MAX, &mut sampler);
+        assert!((transmittance - (-1.5f64).exp()).abs() < 1e-12);
+        let halfway: f64 = medium.transmittance(&r, 0.001, 1.25, &mut sampler);
+        assert!((halfway - (-0.75f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_global() {
+        // The mean free path in fog of density 0.25 is 4.
+        let fog = ConstantMedium::global(0.25, phase());
+        let mut rec = HitRecord::new();
+        let mut sampler = Sampler::new(0);
+        let n: usize = 20000;
+        let mut total: f64 = 0.0;
+        for i in 0..n {
+            let direction = Vec3::new(1.0, i as f64 / n as f64, 0.0);
+            let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), direction);
+            assert!(fog.sample_collision(&r, 0.0, f64::MAX, &mut rec, &mut sampler));
+            total += (rec.p - r.origin()).length();
+        }
+        assert!((total / n as f64 - 4.0).abs() < 0.1, "{}", total / n as f64);
+        assert!(!fog.bounding_box(&mut Aabb::empty()));
+    }
+}
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Six-sided axis-aligned box between two opposite corners, with normals
+/// facing out. Wrap it in an `Instance` to rotate it.
+///
+/// Each face gets surface coordinates from 0 to 1 along the two axes it spans,
+/// in x, y, z order.
+#[derive(Clone)]
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub material: Material,
+}
+
+impl Cuboid {
+    pub fn new(p0: Vec3, p1: Vec3, material: Material) -> Cuboid {
+        Cuboid {
+            min: Vec3::new(p0.x().min(p1.x()), p0.y().min(p1.y()), p0.z().min(p1.z())),
+            max: Vec3::new(p0.x().max(p1.x()), p0.y().max(p1.y()), p0.z().max(p1.z())),
+            material,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (origin, direction) = (r.origin(), r.direction());
+        // Where the ray enters and leaves the box, and through which axis' faces.
+        let (mut t_near, mut near_axis): (f64, usize) = (f64::NEG_INFINITY, 0);
+        let (mut t_far, mut far_axis): (f64, usize) = (f64::INFINITY, 0);
+        for a in 0..3 {
+            let inv_d: f64 = 1.0 / direction[a];
+            let mut t0: f64 = (self.min[a] - origin[a]) * inv_d;
+            let mut t1: f64 = (self.max[a] - origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = a;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = a;
+            }
+        }
+        if t_near > t_far {
+            return false;
+        }
+        let (t, axis, sign): (f64, usize, f64) = if t_near > t_min && t_near < t_max {
+            (t_near, near_axis, -direction[near_axis].signum())
+        } else if t_far > t_min && t_far < t_max {
+            (t_far, far_axis, direction[far_axis].signum())
+        } else {
+            return false;
+        };
+
+        rec.t = t;
+        rec.p = r.point_at_parameter(t);
+        let mut normal: [f64; 3] = [0.0; 3];
+        normal[axis] = sign;
+        rec.normal = Vec3::new(normal[0], normal[1], normal[2]);
+        rec.geometric_normal = rec.normal;
+        let (b, c): (usize, usize) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        rec.u = (rec.p[b] - self.min[b]) / (self.max[b] - self.min[b]);
+        rec.v = (rec.p[c] - self.min[c]) / (self.max[c] - self.min[c]);
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.material);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(self.min, self.max);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn test_hit() {
+        let cuboid = Cuboid::new(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(-1.0, 0.0, -3.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let mut rec = HitRecord::new();
+
+        let r = Ray::new(Vec3::new(0.5, 0.25, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(cuboid.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!((rec.u, rec.v), (0.75, 0.25));
+
+        // Leaving through the bottom from inside.
+        let r = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(cuboid.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 0.5);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+
+        let r = Ray::new(Vec3::new(-5.0, 0.5, -1.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(cuboid.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!cuboid.hit(&r, 0.0, 3.5, &mut rec));
+
+        let r = Ray::new(Vec3::new(2.0, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!cuboid.hit(&r, 0.0, f64::MAX, &mut rec));
+    }
+}
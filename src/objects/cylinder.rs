@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use super::disk::Disk;
+use super::quadric::{phi, solve_quadratic, sweep};
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Cylinder of radius `radius` around the y axis between `y_min` and `y_max`,
+/// open at both ends unless capped. The side normal faces away from the axis.
+///
+/// `u` runs from 0 to 1 around the sweep and `v` from 0 at `y_min` to 1 at
+/// `y_max`.
+#[derive(Clone)]
+pub struct Cylinder {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    /// Sweep in radians.
+    pub phi_max: f64,
+    pub material: Material,
+    /// Bottom and top caps, facing out.
+    caps: Option<(Disk, Disk)>,
+}
+
+impl Cylinder {
+    pub fn new(radius: f64, y_min: f64, y_max: f64, material: Material) -> Cylinder {
+        Cylinder {
+            radius,
+            y_min: y_min.min(y_max),
+            y_max: y_min.max(y_max),
+            phi_max: 2.0 * PI,
+            material,
+            caps: None,
+        }
+    }
+
+    /// Cuts the cylinder down to a sector of `degrees`, caps included.
+    pub fn phi_max(mut self, degrees: f64) -> Cylinder {
+        self.phi_max = sweep(degrees);
+        self.caps = self
+            .caps
+            .map(|(bottom, top)| (bottom.phi_max(degrees), top.phi_max(degrees)));
+        self
+    }
+
+    /// Closes both ends with disks of the same material.
+    pub fn capped(mut self) -> Cylinder {
+        let degrees: f64 = self.phi_max.to_degrees();
+        let bottom = Disk::new(self.y_min, self.radius, self.material.clone())
+            .phi_max(degrees)
+            .flip();
+        let top = Disk::new(self.y_max, self.radius, self.material.clone()).phi_max(degrees);
+        self.caps = Some((bottom, top));
+        self
+    }
+
+    fn hit_side<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (o, d) = (r.origin(), r.direction());
+        let a: f64 = d.x() * d.x() + d.z() * d.z();
+        let b: f64 = 2.0 * (o.x() * d.x() + o.z() * d.z());
+        let c: f64 = o.x() * o.x() + o.z() * o.z() - self.radius * self.radius;
+        let (t0, t1) = match solve_quadratic(a, b, c) {
+            Some(roots) => roots,
+            None => return false,
+        };
+        for t in [t0, t1].iter() {
+            if *t <= t_min || *t >= t_max {
+                continue;
+            }
+            let p: Vec3 = r.point_at_parameter(*t);
+            let phi: f64 = phi(&p);
+            if p.y() < self.y_min || p.y() > self.y_max || phi > self.phi_max {
+                continue;
+            }
+            rec.t = *t;
+            rec.p = p;
+            rec.normal = Vec3::new(p.x(), 0.0, p.z()) / self.radius;
+            rec.geometric_normal = rec.normal;
+            rec.u = phi / self.phi_max;
+            rec.v = (p.y() - self.y_min) / (self.y_max - self.y_min);
+            rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+            rec.material = Cow::Borrowed(&self.material);
+            return true;
+        }
+        false
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let mut closest: f64 = t_max;
+        let mut hit_anything: bool = false;
+        if self.hit_side(r, t_min, closest, rec) {
+            closest = rec.t;
+            hit_anything = true;
+        }
+        if let Some((bottom, top)) = &self.caps {
+            for cap in [bottom, top].iter() {
+                if cap.hit(r, t_min, closest, rec) {
+                    closest = rec.t;
+                    hit_anything = true;
+                }
+            }
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Vec3::new(-self.radius, self.y_min, -self.radius),
+            Vec3::new(self.radius, self.y_max, self.radius),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn cylinder() -> Cylinder {
+        Cylinder::new(
+            1.0,
+            2.0,
+            -1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let cylinder = cylinder();
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(cylinder.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!((rec.u, rec.v), (0.0, 0.5));
+
+        // From inside, the far wall faces away from the ray.
+        let r = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(cylinder.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // Open ends let a ray along the axis through.
+        let down = Ray::new(Vec3::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!cylinder.hit(&down, 0.0, f64::MAX, &mut rec));
+        let capped = cylinder.clone().capped();
+        assert!(capped.hit(&down, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 3.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        let mut bbox = Aabb::empty();
+        assert!(capped.bounding_box(&mut bbox));
+        assert_eq!(bbox.min, Vec3::new(-1.0, -1.0, -1.0));
+        assert_eq!(bbox.max, Vec3::new(1.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn test_phi_max() {
+        // Half a cylinder keeps the -z side, so a ray from +z hits the back wall.
+        let half = cylinder().phi_max(180.0).capped();
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(0.0, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(half.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 6.0);
+        assert!((rec.u - 0.5).abs() < 1e-12);
+
+        // The caps are cut the same way.
+        let down = |z: f64| Ray::new(Vec3::new(0.0, 5.0, z), Vec3::new(0.0, -1.0, 0.0));
+        assert!(half.hit(&down(-0.5), 0.0, f64::MAX, &mut rec));
+        assert!(!half.hit(&down(0.5), 0.0, f64::MAX, &mut rec));
+    }
+}
@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use super::quadric::{phi, sweep};
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Padding that keeps the box of a disk from being flat.
+const BOX_PADDING: f64 = 0.0001;
+
+/// Disk of radius `radius` around the y axis at height `height`, facing +y
+/// unless flipped. A nonzero `inner_radius` makes it an annulus.
+///
+/// `u` runs from 0 to 1 around the sweep and `v` from 0 at the rim to 1 at
+/// the inner edge.
+#[derive(Clone)]
+pub struct Disk {
+    pub height: f64,
+    pub radius: f64,
+    pub inner_radius: f64,
+    /// Sweep in radians.
+    pub phi_max: f64,
+    pub flipped: bool,
+    pub material: Material,
+}
+
+impl Disk {
+    pub fn new(height: f64, radius: f64, material: Material) -> Disk {
+        Disk {
+            height,
+            radius,
+            inner_radius: 0.0,
+            phi_max: 2.0 * PI,
+            flipped: false,
+            material,
+        }
+    }
+
+    pub fn inner_radius(mut self, inner_radius: f64) -> Disk {
+        self.inner_radius = inner_radius.clamp(0.0, self.radius);
+        self
+    }
+
+    /// Cuts the disk down to a sector of `degrees`.
+    pub fn phi_max(mut self, degrees: f64) -> Disk {
+        self.phi_max = sweep(degrees);
+        self
+    }
+
+    /// Turns the disk to face -y.
+    pub fn flip(mut self) -> Disk {
+        self.flipped = !self.flipped;
+        self
+    }
+}
+
+impl Hittable for Disk {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let t: f64 = (self.height - r.origin().y()) / r.direction().y();
+        // Also rejects the NaN of rays parallel to the disk.
+        if !(t > t_min && t < t_max) {
+            return false;
+        }
+        let p: Vec3 = r.point_at_parameter(t);
+        let distance_squared: f64 = p.x() * p.x() + p.z() * p.z();
+        if distance_squared > self.radius * self.radius
+            || distance_squared < self.inner_radius * self.inner_radius
+        {
+            return false;
+        }
+        let phi: f64 = phi(&p);
+        if phi > self.phi_max {
+            return false;
+        }
+        rec.t = t;
+        rec.p = p;
+        rec.normal = Vec3::new(0.0, if self.flipped { -1.0 } else { 1.0 }, 0.0);
+        rec.geometric_normal = rec.normal;
+        rec.u = phi / self.phi_max;
+        rec.v = (self.radius - distance_squared.sqrt()) / (self.radius - self.inner_radius);
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.material);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Vec3::new(-self.radius, self.height - BOX_PADDING, -self.radius),
+            Vec3::new(self.radius, self.height + BOX_PADDING, self.radius),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn test_hit() {
+        let material = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let annulus = Disk::new(1.0, 2.0, material).inner_radius(1.0);
+        let mut rec = HitRecord::new();
+        let down = |x: f64, z: f64| Ray::new(Vec3::new(x, 3.0, z), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(annulus.hit(&down(1.5, 0.0), 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!((rec.u, rec.v), (0.0, 0.5));
+        assert!(!annulus.hit(&down(0.5, 0.0), 0.0, f64::MAX, &mut rec));
+        assert!(!annulus.hit(&down(2.5, 0.0), 0.0, f64::MAX, &mut rec));
+
+        // A quarter sector covers -z of +x only.
+        let quarter = annulus.clone().phi_max(90.0).flip();
+        assert!(quarter.hit(&down(1.0, -1.0), 0.0, f64::MAX, &mut rec));
+        assert!((rec.u - 0.5).abs() < 1e-12);
+        assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert!(!quarter.hit(&down(1.0, 1.0), 0.0, f64::MAX, &mut rec));
+        assert!(!quarter.hit(&down(-1.0, -1.0), 0.0, f64::MAX, &mut rec));
+    }
+}
@@ -0,0 +1,325 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+use crate::structs::voxel_grid::VoxelGrid;
+
+/// Second radiation constant `h c / k`, in metre kelvin.
+const PLANCK_C2: f64 = 1.438_777e-2;
+/// Wien's displacement constant, in metre kelvin.
+const WIEN_B: f64 = 2.897_772e-3;
+/// Wavelengths, in metres, that stand in for the red, green and blue channels.
+const RGB_WAVELENGTHS: [f64; 3] = [610e-9, 550e-9, 465e-9];
+
+/// Color of a black body at `kelvin`, from Planck's law at one wavelength per
+/// channel. It is divided by the peak of the whole spectrum, so every channel
+/// stays within 0..1 and bodies too cold to glow visibly come out black.
+pub fn blackbody(kelvin: f64) -> Vec3 {
+    if kelvin <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let peak: f64 = WIEN_B / kelvin;
+    let relative = |lambda: f64| -> f64 {
+        (peak / lambda).powi(5) * (PLANCK_C2 / (peak * kelvin)).exp_m1()
+            / (PLANCK_C2 / (lambda * kelvin)).exp_m1()
+    };
+    Vec3::new(
+        relative(RGB_WAVELENGTHS[0]),
+        relative(RGB_WAVELENGTHS[1]),
+        relative(RGB_WAVELENGTHS[2]),
+    )
+}
+
+/// Participating medium whose density varies over a voxel grid, for smoke,
+/// clouds and fire. Place it with an `Instance`.
+///
+/// Rays are stopped by delta tracking against the largest density in the
+/// grid, and shadow rays are dimmed by ratio tracking, both of which are
+/// unbiased. Like `ConstantMedium` it is only seen by `sample_collision`.
+///
+/// An emission grid, with one grey or three RGB channels, and a temperature
+/// grid, glowing as a black body, make the medium emit light. Both are added
+/// at every collision, so the light grows with the density, and both are
+/// multiplied by `emission_scale`. The emission is carried by the phase
+/// function, which has to be `Material::Isotropic` or
+/// `Material::HenyeyGreenstein` for it to show.
+#[derive(Clone)]
+pub struct GridMedium {
+    density: Arc<VoxelGrid>,
+    density_scale: f64,
+    /// Largest density anywhere in the grid.
+    majorant: f64,
+    pub phase_function: Material,
+    pub emission: Option<Arc<VoxelGrid>>,
+    pub temperature: Option<Arc<VoxelGrid>>,
+    /// Kelvin per unit of the temperature grid.
+    pub temperature_scale: f64,
+    pub emission_scale: f64,
+}
+
+impl GridMedium {
+    /// Medium with `density_scale` times the first channel of `density`
+    /// within the bounds of the grid.
+    pub fn new(
+        density: Arc<VoxelGrid>,
+        density_scale: f64,
+        phase_function: Material,
+    ) -> GridMedium {
+        let majorant: f64 = density.max_value(0) * density_scale;
+        GridMedium {
+            density,
+            density_scale,
+            majorant,
+            phase_function,
+            emission: None,
+            temperature: None,
+            temperature_scale: 1.0,
+            emission_scale: 1.0,
+        }
+    }
+
+    pub fn emission(mut self, emission: Arc<VoxelGrid>) -> GridMedium {
+        self.emission = Some(emission);
+        self
+    }
+
+    pub fn temperature(mut self, temperature: Arc<VoxelGrid>, kelvin_per_unit: f64) -> GridMedium {
+        self.temperature = Some(temperature);
+        self.temperature_scale = kelvin_per_unit;
+        self
+    }
+
+    pub fn emission_scale(mut self, emission_scale: f64) -> GridMedium {
+        self.emission_scale = emission_scale;
+        self
+    }
+
+    pub fn density_at(&self, p: &Vec3) -> f64 {
+        self.density.value(p, 0) * self.density_scale
+    }
+
+    /// Light added by a collision at `p`.
+    pub fn emission_at(&self, p: &Vec3) -> Vec3 {
+        let mut emission = Vec3::new(0.0, 0.0, 0.0);
+        if let Some(grid) = &self.emission {
+            emission += if grid.channels >= 3 {
+                Vec3::new(grid.value(p, 0), grid.value(p, 1), grid.value(p, 2))
+            } else {
+                let value: f64 = grid.value(p, 0);
+                Vec3::new(value, value, value)
+            };
+        }
+        if let Some(grid) = &self.temperature {
+            emission += blackbody(grid.value(p, 0) * self.temperature_scale);
+        }
+        emission * self.emission_scale
+    }
+
+    /// Distance along `r`, in units of `t`, to the next tentative collision
+    /// against the majorant.
+    fn step(&self, r: &Ray, sampler: &mut Sampler) -> f64 {
+        -(1.0 - sampler.gen::<f64>()).ln() / (self.majorant * r.direction().length())
+    }
+
+    /// The phase function, glowing with the light emitted at `p`.
+    fn collision_material(&self, p: &Vec3) -> Cow<'_, Material> {
+        if self.emission.is_none() && self.temperature.is_none() {
+            return Cow::Borrowed(&self.phase_function);
+        }
+        let emission: Vec3 = self.emission_at(p);
+        match &self.phase_function {
+            Material::Isotropic(phase) => {
+                let mut phase = phase.clone();
+                phase.emission = emission;
+                Cow::Owned(Material::Isotropic(phase))
+            }
+            Material::HenyeyGreenstein(phase) => {
+                let mut phase = phase.clone();
+                phase.emission = emission;
+                Cow::Owned(Material::HenyeyGreenstein(phase))
+            }
+            other => Cow::Borrowed(other),
+        }
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit<'a>(&'a self, _r: &Ray, _t_min: f64, _t_max: f64, _rec: &mut HitRecord<'a>) -> bool {
+        false
+    }
+
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let (enter, exit) = match self.density.bounds.clip(r, t_min, t_max) {
+            Some(range) => range,
+            None => return false,
+        };
+        if self.majorant <= 0.0 {
+            return false;
+        }
+        // Delta tracking: step through tentative collisions and accept each
+        // with the ratio of the density there to the majorant.
+        let mut t: f64 = enter;
+        loop {
+            t += self.step(r, sampler);
+            if t >= exit {
+                return false;
+            }
+            let p: Vec3 = r.point_at_parameter(t);
+            if sampler.gen::<f64>() * self.majorant < self.density_at(&p) {
+                break;
+            }
+        }
+        rec.t = t;
+        rec.p = r.point_at_parameter(t);
+        // Phase functions ignore the normal; this one faces back along the ray.
+        rec.normal = -Vec3::unit_vector(&r.direction());
+        rec.geometric_normal = rec.normal;
+        rec.u = 0.0;
+        rec.v = 0.0;
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = self.collision_material(&rec.p);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.density.bounds;
+        true
+    }
+
+    /// Ratio tracking: the product of the chances of passing every tentative
+    /// collision. Paths that have become dim are ended by Russian roulette.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, sampler: &mut Sampler) -> f64 {
+        let (enter, exit) = match self.density.bounds.clip(r, t_min, t_max) {
+            Some(range) => range,
+            None => return 1.0,
+        };
+        if self.majorant <= 0.0 {
+            return 1.0;
+        }
+        let mut transmittance: f64 = 1.0;
+        let mut t: f64 = enter;
+        loop {
+            t += self.step(r, sampler);
+            if t >= exit {
+                return transmittance;
+            }
+            let p: Vec3 = r.point_at_parameter(t);
+            transmittance *= 1.0 - self.density_at(&p) / self.majorant;
+            if transmittance < 0.1 {
+                if sampler.gen::<f64>() < 0.5 {
+                    return 0.0;
+                }
+                transmittance *= 2.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::isotropic::Isotropic;
+    use crate::materials::Scatterable;
+
+    /// Unit cube whose density rises linearly from 0 at x = 0 to 2 at x = 1,
+    /// so a ray along x crosses an optical depth of 1.
+    fn ramp() -> GridMedium {
+        let grid = VoxelGrid::new(
+            [2, 2, 2],
+            1,
+            Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+            vec![0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0],
+        );
+        GridMedium::new(
+            Arc::new(grid),
+            2.0,
+            Material::Isotropic(Isotropic::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_tracking() {
+        let medium = ramp();
+        let expected: f64 = (-1.0f64).exp();
+        let mut rec = HitRecord::new();
+        let mut sampler = Sampler::new(0);
+        let n: usize = 20000;
+        let (mut passed, mut transmittance, mut depth): (usize, f64, f64) = (0, 0.0, 0.0);
+        let r = Ray::new(Vec3::new(-1.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        for _ in 0..n {
+            if medium.sample_collision(&r, 0.001, f64::MAX, &mut rec, &mut sampler) {
+                assert!(rec.p.x() > 0.0 && rec.p.x() < 1.0);
+                depth += rec.p.x();
+            } else {
+                passed += 1;
+            }
+            transmittance += medium.transmittance(&r, 0.001, f64::MAX, &mut sampler);
+        }
+        let passed: f64 = passed as f64 / n as f64;
+        assert!((passed - expected).abs() < 0.01, "{}", passed);
+        assert!((transmittance / n as f64 - expected).abs() < 0.01);
+        // Collisions follow 2 x exp(-x^2), which gathers them towards x = 1.
+        let mean_depth: f64 = depth / (n as f64 * (1.0 - passed));
+        assert!(mean_depth > 0.5, "{}", mean_depth);
+
+        // Rays that stay in the thin half, or miss the grid, get through.
+        let r = Ray::new(Vec3::new(0.0, 0.5, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!medium.sample_collision(&r, 0.001, f64::MAX, &mut rec, &mut sampler));
+        assert_eq!(medium.transmittance(&r, 0.001, f64::MAX, &mut sampler), 1.0);
+        let r = Ray::new(Vec3::new(-1.0, 2.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(medium.transmittance(&r, 0.001, f64::MAX, &mut sampler), 1.0);
+    }
+
+    #[test]
+    fn test_emission() {
+        let temperature = Arc::new(VoxelGrid::new(
+            [1, 1, 1],
+            1,
+            Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+            vec![1.5],
+        ));
+        let medium = ramp().temperature(temperature, 1000.0).emission_scale(10.0);
+        let fire: Vec3 = medium.emission_at(&Vec3::new(0.5, 0.5, 0.5));
+        assert_eq!(fire, blackbody(1500.0) * 10.0);
+        assert!(fire.x() > fire.y() && fire.y() > fire.z());
+
+        let mut rec = HitRecord::new();
+        let mut sampler = Sampler::new(0);
+        let r = Ray::new(Vec3::new(1.5, 0.5, 0.5), Vec3::new(-1.0, 0.0, 0.0));
+        let mut hit: bool = false;
+        for _ in 0..100 {
+            if medium.sample_collision(&r, 0.001, f64::MAX, &mut rec, &mut sampler) {
+                assert_eq!(rec.material.emitted(&r, &rec), fire);
+                hit = true;
+            }
+        }
+        assert!(hit);
+    }
+
+    #[test]
+    fn test_blackbody() {
+        assert_eq!(blackbody(0.0), Vec3::new(0.0, 0.0, 0.0));
+        // Too cold to glow, then red, then close to white.
+        assert!(blackbody(300.0).x() < 1e-20);
+        let warm: Vec3 = blackbody(2000.0);
+        assert!(warm.x() > 2.0 * warm.z());
+        let white: Vec3 = blackbody(6500.0);
+        for a in 0..3 {
+            assert!(white[a] > 0.8 && white[a] <= 1.0, "{:?}", white);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::transform::Transform;
+use crate::structs::vec3::Vec3;
+
+/// A shared object placed in the world by an affine transform.
+///
+/// Rays are moved into the object's own space and hits moved back, so any
+/// number of instances can refer to one object, e.g. a large mesh, without
+/// copying it.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    /// Object-to-world transform.
+    transform: Transform,
+    bbox: Option<Aabb>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Instance {
+        let mut object_box = Aabb::empty();
+        let bbox: Option<Aabb> = if object.bounding_box(&mut object_box) {
+            Some(transform.bounding_box(&object_box))
+        } else {
+            None
+        };
+        Instance {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// `r` in object space. The direction is not renormalized, so `t` is the
+    /// same in both spaces.
+    fn local_ray(&self, r: &Ray) -> Ray {
+        Ray::with_time(
+            self.transform.inverse.transform_point(&r.origin()),
+            self.transform.inverse.transform_vector(&r.direction()),
+            r.time(),
+        )
+    }
+
+    /// Moves a hit on the object into world space.
+    fn to_world(&self, rec: &mut HitRecord) {
+        rec.p = self.transform.point(&rec.p);
+        rec.normal = Vec3::unit_vector(&self.transform.normal(&rec.normal));
+        rec.geometric_normal = Vec3::unit_vector(&self.transform.normal(&rec.geometric_normal));
+    }
+}
+
+impl Hittable for Instance {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        if !self.object.hit(&self.local_ray(r), t_min, t_max, rec) {
+            return false;
+        }
+        self.to_world(rec);
+        true
+    }
+
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        sampler: &mut Sampler,
+    ) -> bool {
+        if !self
+            .object
+            .sample_collision(&self.local_ray(r), t_min, t_max, rec, sampler)
+        {
+            return false;
+        }
+        self.to_world(rec);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        match self.bbox {
+            Some(bbox) => {
+                *output_box = bbox;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The object's density for the direction as seen in object space, times
+    /// the Jacobian `|det A| / |A w|^3` of mapping unit directions `w` with
+    /// the linear part `A` of the world-to-object transform.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let inverse = &self.transform.inverse;
+        let local: Vec3 = inverse.transform_vector(&Vec3::unit_vector(direction));
+        let pdf: f64 = self
+            .object
+            .pdf_value(&inverse.transform_point(origin), &local);
+        pdf * inverse.determinant3().abs() / local.length().powi(3)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        let local_origin: Vec3 = self.transform.inverse.transform_point(origin);
+        self.object
+            .random(&local_origin, sampler)
+            .map(|local| self.transform.vector(&local))
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, sampler: &mut Sampler) -> f64 {
+        self.object
+            .transmittance(&self.local_ray(r), t_min, t_max, sampler)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::objects::bvh::BvhNode;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::triangle::Triangle;
+    use rand::Rng;
+
+    fn material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn test_hit() {
+        let sphere: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, material()));
+        let mut list = HittableList::new();
+        for i in 0..3 {
+            let transform = Transform::scale(Vec3::new(1.0, 2.0, 1.0))
+                .then(&Transform::translate(Vec3::new(3.0 * i as f64, 0.0, -5.0)));
+            list.push(Instance::new(Arc::clone(&sphere), transform));
+        }
+        let world = BvhNode::new(list);
+
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(6.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.p, Vec3::new(6.0, 0.0, -4.0));
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // Stretched along y, so the ellipsoid is hit higher up than the sphere.
+        let r = Ray::new(Vec3::new(0.0, 1.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.p.y() - 1.5).abs() < 1e-12);
+        assert!((rec.normal.length() - 1.0).abs() < 1e-12);
+
+        let mut bbox = Aabb::empty();
+        assert!(world.bounding_box(&mut bbox));
+        assert_eq!(bbox.min, Vec3::new(-1.0, -2.0, -6.0));
+        assert_eq!(bbox.max, Vec3::new(7.0, 2.0, -4.0));
+    }
+
+    #[test]
+    fn test_normal() {
+        // A 45 degree triangle squashed along y: the normal must stay
+        // perpendicular to the surface, not simply be squashed too.
+        let triangle: Arc<dyn Hittable> = Arc::new(Triangle::new(
+            Vec3::new(-1.0, -1.0, -1.0),
+            Vec3::new(1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            material(),
+        ));
+        let instance = Instance::new(triangle, Transform::scale(Vec3::new(1.0, 1.0, 0.5)));
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(instance.hit(&r, 0.0, f64::MAX, &mut rec));
+        let expected: Vec3 = Vec3::unit_vector(&Vec3::new(-1.0, 0.0, 2.0));
+        assert!((rec.normal.length() - 1.0).abs() < 1e-12);
+        assert!((Vec3::dot(&rec.normal, &expected).abs() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pdf_value() {
+        // The density of `random` integrates to one over the sphere of
+        // directions, also after a non-uniform scale.
+        let triangle: Arc<dyn Hittable> = Arc::new(Triangle::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, -1.0),
+            material(),
+        ));
+        let transform = Transform::scale(Vec3::new(2.0, 0.5, 1.5))
+            .then(&Transform::rotate(Vec3::new(1.0, 1.0, 0.0), 30.0));
+        let instance = Instance::new(triangle, transform);
+        let origin = Vec3::new(0.1, 0.2, 0.3);
+        let mut sampler = Sampler::new(0);
+        let n: usize = 200_000;
+        let mut estimate: f64 = 0.0;
+        for _ in 0..n {
+            // Uniform directions have density 1 / (4 pi).
+            let z: f64 = sampler.gen_range(-1.0, 1.0);
+            let phi: f64 = sampler.gen_range(0.0, 2.0 * std::f64::consts::PI);
+            let s: f64 = (1.0 - z * z).sqrt();
+            let direction = Vec3::new(s * phi.cos(), s * phi.sin(), z);
+            estimate += instance.pdf_value(&origin, &direction) * 4.0 * std::f64::consts::PI;
+        }
+        assert!((estimate / n as f64 - 1.0).abs() < 0.05);
+
+        let direction: Vec3 = instance.random(&origin, &mut sampler).unwrap();
+        assert!(instance.pdf_value(&origin, &direction) > 0.0);
+    }
+}
@@ -0,0 +1,269 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::triangle;
+use super::*;
+use crate::materials::lambertian::Lambertian;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Indexed triangle mesh. Faces share the vertex buffers, so each face costs
+/// three indices instead of three copies of every vertex attribute.
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3>,
+    /// Per-vertex shading normals; either empty or as long as `positions`.
+    pub normals: Vec<Vec3>,
+    /// Per-vertex texture coordinates; either empty or as long as `positions`.
+    pub uvs: Vec<(f64, f64)>,
+    /// Per-vertex colors; either empty or as long as `positions`. When present they
+    /// replace the albedo of a `Lambertian` material.
+    pub colors: Vec<Vec3>,
+    pub indices: Vec<[usize; 3]>,
+    pub material: Material,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Vec3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f64, f64)>,
+        indices: Vec<[usize; 3]>,
+        material: Material,
+    ) -> TriangleMesh {
+        assert!(normals.is_empty() || normals.len() == positions.len());
+        assert!(uvs.is_empty() || uvs.len() == positions.len());
+        assert!(indices.iter().flatten().all(|i| *i < positions.len()));
+        TriangleMesh {
+            positions,
+            normals,
+            uvs,
+            colors: Vec::new(),
+            indices,
+            material,
+        }
+    }
+
+    pub fn with_colors(mut self, colors: Vec<Vec3>) -> TriangleMesh {
+        assert!(colors.is_empty() || colors.len() == self.positions.len());
+        self.colors = colors;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// One hittable per face, all referring to `mesh`. Build a `BvhNode` from the
+    /// result to trace large meshes.
+    pub fn triangles(mesh: &Arc<TriangleMesh>) -> HittableList {
+        let mut list = HittableList::new();
+        for index in 0..mesh.len() {
+            list.push(MeshTriangle::new(Arc::clone(mesh), index));
+        }
+        list
+    }
+}
+
+/// Face `index` of a shared `TriangleMesh`.
+pub struct MeshTriangle {
+    mesh: Arc<TriangleMesh>,
+    index: usize,
+}
+
+impl MeshTriangle {
+    pub fn new(mesh: Arc<TriangleMesh>, index: usize) -> MeshTriangle {
+        assert!(index < mesh.len());
+        MeshTriangle { mesh, index }
+    }
+
+    fn vertices(&self) -> (&Vec3, &Vec3, &Vec3) {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        let p = &self.mesh.positions;
+        (&p[i0], &p[i1], &p[i2])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (v0, v1, v2) = self.vertices();
+        let (t, b1, b2) = match triangle::intersect(r, v0, v1, v2, t_min, t_max) {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let b0: f64 = 1.0 - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+
+        rec.t = t;
+        rec.p = r.point_at_parameter(t);
+        rec.geometric_normal = Vec3::unit_vector(&Vec3::cross(&(*v1 - *v0), &(*v2 - *v0)));
+        rec.normal = if self.mesh.normals.is_empty() {
+            rec.geometric_normal
+        } else {
+            let n = &self.mesh.normals;
+            let shading: Vec3 = Vec3::unit_vector(&(n[i0] * b0 + n[i1] * b1 + n[i2] * b2));
+            // The vertex normals give the authored orientation, so the winding
+            // gives way when the two disagree.
+            if Vec3::dot(&shading, &rec.geometric_normal) < 0.0 {
+                rec.geometric_normal = -rec.geometric_normal;
+            }
+            shading
+        };
+        if self.mesh.uvs.is_empty() {
+            rec.u = b1;
+            rec.v = b2;
+        } else {
+            let uv = &self.mesh.uvs;
+            rec.u = uv[i0].0 * b0 + uv[i1].0 * b1 + uv[i2].0 * b2;
+            rec.v = uv[i0].1 * b0 + uv[i1].1 * b1 + uv[i2].1 * b2;
+        }
+        rec.barycentric = Vec3::new(b0, b1, b2);
+        rec.material = match &self.mesh.material {
+            Material::Lambertian(_) if !self.mesh.colors.is_empty() => {
+                let c = &self.mesh.colors;
+                Cow::Owned(Material::Lambertian(Lambertian::new(
+                    c[i0] * b0 + c[i1] * b1 + c[i2] * b2,
+                )))
+            }
+            material => Cow::Borrowed(material),
+        };
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let (v0, v1, v2) = self.vertices();
+        *output_box = triangle::bounding_box(v0, v1, v2);
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let (v0, v1, v2) = self.vertices();
+        triangle::pdf_value(v0, v1, v2, origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        let (v0, v1, v2) = self.vertices();
+        Some(triangle::random_point(v0, v1, v2, sampler) - *origin)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::objects::bvh::BvhNode;
+    use crate::textures::Texture;
+
+    fn mesh_positions() -> Vec<Vec3> {
+        vec![
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 0.0, -1.0),
+            Vec3::new(1.0, 1.0, -1.0),
+            Vec3::new(0.0, 1.0, -1.0),
+        ]
+    }
+
+    /// Unit square in the z = -1 plane made of two triangles.
+    fn quad(normals: Vec<Vec3>, uvs: Vec<(f64, f64)>) -> Arc<TriangleMesh> {
+        Arc::new(TriangleMesh::new(
+            mesh_positions(),
+            normals,
+            uvs,
+            vec![[0, 1, 2], [0, 2, 3]],
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    #[test]
+    fn test_hit_mesh() {
+        let mesh = quad(Vec::new(), Vec::new());
+        let world = BvhNode::new(TriangleMesh::triangles(&mesh));
+        let mut rec = HitRecord::new();
+
+        let r = Ray::new(Vec3::new(0.75, 0.25, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(rec.barycentric, Vec3::new(0.25, 0.5, 0.25));
+
+        let r = Ray::new(Vec3::new(0.25, 0.75, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.barycentric, Vec3::new(0.25, 0.25, 0.5));
+
+        let r = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!world.hit(&r, 0.0, f64::MAX, &mut rec));
+    }
+
+    #[test]
+    fn test_interpolated_attributes() {
+        let normals = vec![
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        let uvs = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+        let mesh = quad(normals, uvs);
+        let face = MeshTriangle::new(Arc::clone(&mesh), 0);
+        let mut rec = HitRecord::new();
+
+        let r = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(face.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.normal, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(rec.geometric_normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!((rec.u, rec.v), (2.0, 0.0));
+    }
+
+    #[test]
+    fn test_clockwise_winding() {
+        // Wound clockwise seen from +z, but the vertex normals point to +z.
+        let mesh = Arc::new(TriangleMesh::new(
+            mesh_positions(),
+            vec![Vec3::new(0.0, 0.0, 1.0); 4],
+            Vec::new(),
+            vec![[0, 2, 1]],
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let face = MeshTriangle::new(mesh, 0);
+        let mut rec = HitRecord::new();
+
+        let r = Ray::new(Vec3::new(0.75, 0.25, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(face.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(rec.geometric_normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_vertex_colors() {
+        let colors = vec![
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        let mesh = TriangleMesh::new(
+            mesh_positions(),
+            Vec::new(),
+            Vec::new(),
+            vec![[0, 1, 2], [0, 2, 3]],
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+        .with_colors(colors);
+        let face = MeshTriangle::new(Arc::new(mesh), 0);
+        let mut rec = HitRecord::new();
+
+        let r = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(face.hit(&r, 0.0, f64::MAX, &mut rec));
+        match &*rec.material {
+            Material::Lambertian(l) => {
+                assert_eq!(l.albedo, Texture::Solid(Vec3::new(0.0, 1.0, 0.0)))
+            }
+            _ => panic!("expected Lambertian"),
+        }
+    }
+}
@@ -1,60 +1,164 @@
+use std::borrow::Cow;
 use std::f64;
+use std::sync::Arc;
+
+use rand::Rng;
 
-use self::sphere::Sphere;
 use crate::materials::lambertian::Lambertian;
-use crate::materials::metal::Metal;
 use crate::materials::Material;
+use crate::structs::aabb::Aabb;
 use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
 use crate::structs::vec3::Vec3;
 
+pub mod bvh;
 pub mod camera;
+pub mod cone;
+pub mod constant_medium;
+pub mod cuboid;
+pub mod cylinder;
+pub mod disk;
+pub mod grid_medium;
+pub mod instance;
+pub mod mesh;
+pub mod moving_sphere;
+pub mod paraboloid;
+pub mod plane;
+pub mod quad;
+pub mod quadric;
+pub mod rect;
 pub mod sphere;
+pub mod torus;
+pub mod triangle;
 
-#[derive(Copy, Clone)]
-pub struct HitRecord {
+#[derive(Clone)]
+pub struct HitRecord<'a> {
     pub t: f64,
     pub p: Vec3,
+    /// Shading normal, interpolated from vertex normals on meshes that have them.
     pub normal: Vec3,
-    pub material: Material,
+    /// Normal of the actual surface that was hit.
+    pub geometric_normal: Vec3,
+    /// Surface coordinates of `p`.
+    pub u: f64,
+    pub v: f64,
+    /// Weights of the three vertices at `p` when a triangle was hit.
+    pub barycentric: Vec3,
+    /// Borrowed from the object that was hit, unless the object made up a
+    /// material for this hit, e.g. from interpolated vertex colors.
+    pub material: Cow<'a, Material>,
 }
 
-impl HitRecord {
+impl<'a> HitRecord<'a> {
     pub fn new() -> Self {
         let lambert = Material::Lambertian(Lambertian::new(Vec3::new(0.0, 0.0, 0.0)));
         HitRecord {
             t: 0.0,
             p: Vec3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
-            material: lambert,
+            geometric_normal: Vec3::new(0.0, 0.0, 0.0),
+            u: 0.0,
+            v: 0.0,
+            barycentric: Vec3::new(0.0, 0.0, 0.0),
+            material: Cow::Owned(lambert),
         }
     }
 }
 
-pub trait Hittable {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
+impl<'a> Default for HitRecord<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Anything a ray can hit. `Send + Sync` so that worlds can be shared between threads.
+pub trait Hittable: Send + Sync {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool;
+
+    /// Writes a box enclosing the whole object into `output_box`.
+    /// Returns `false` if the object has no finite bounds.
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool;
+
+    /// Solid-angle density with which `random` picks `direction` from `origin`.
+    /// Objects that cannot be sampled as lights return 0.
+    fn pdf_value(&self, _origin: &Vec3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Direction from `origin` towards a random point of the object, distributed
+    /// as given by `pdf_value`, or `None` for objects that cannot be sampled.
+    fn random(&self, _origin: &Vec3, _sampler: &mut Sampler) -> Option<Vec3> {
+        None
+    }
+
+    /// Like `hit`, but participating media may also stop `r` at a collision
+    /// drawn from `sampler`. Media have no surface that `hit` could report,
+    /// so everything that should see them traces rays through this.
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        _sampler: &mut Sampler,
+    ) -> bool {
+        self.hit(r, t_min, t_max, rec)
+    }
+
+    /// Fraction of light that gets through along `r` between `t_min` and
+    /// `t_max`. Surfaces block it completely. Media may return a random
+    /// estimate that is only right on average.
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, _sampler: &mut Sampler) -> f64 {
+        let mut rec = HitRecord::new();
+        if self.hit(r, t_min, t_max, &mut rec) {
+            0.0
+        } else {
+            1.0
+        }
+    }
 }
 
 pub struct HittableList {
-    list: Vec<Sphere>,
+    list: Vec<Arc<dyn Hittable>>,
 }
 
 impl HittableList {
     pub fn new() -> HittableList {
-        let list: Vec<Sphere> = Vec::new();
-        HittableList { list: list }
+        let list: Vec<Arc<dyn Hittable>> = Vec::new();
+        HittableList { list }
     }
 
-    pub fn push(&mut self, s: Sphere) {
-        self.list.push(s);
+    pub fn push<H: Hittable + 'static>(&mut self, object: H) {
+        self.list.push(Arc::new(object));
+    }
+
+    /// Adds an object that may also be referenced from elsewhere, e.g. by several lists.
+    pub fn push_shared(&mut self, object: Arc<dyn Hittable>) {
+        self.list.push(object);
+    }
+
+    /// Moves all objects of `other` into this list.
+    pub fn append(&mut self, mut other: HittableList) {
+        self.list.append(&mut other.list);
     }
 
     pub fn size(&self) -> usize {
         self.list.len()
     }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.list
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
         let mut temp_rec = HitRecord::new();
         let mut hit_anything = false;
         let mut closest_so_far = t_max;
@@ -62,15 +166,92 @@ impl Hittable for HittableList {
             if self.list[i].hit(r, t_min, closest_so_far, &mut temp_rec) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
-                *rec = temp_rec;
+                *rec = temp_rec.clone();
+            }
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        if self.list.is_empty() {
+            return false;
+        }
+        let mut temp_box = Aabb::empty();
+        let mut first_box = true;
+        for object in self.list.iter() {
+            if !object.bounding_box(&mut temp_box) {
+                return false;
+            }
+            *output_box = if first_box {
+                temp_box
+            } else {
+                Aabb::surrounding_box(output_box, &temp_box)
+            };
+            first_box = false;
+        }
+        true
+    }
+
+    fn sample_collision<'a>(
+        &'a self,
+        r: &Ray,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord<'a>,
+        sampler: &mut Sampler,
+    ) -> bool {
+        let mut temp_rec = HitRecord::new();
+        let mut hit_anything = false;
+        let mut closest_so_far = t_max;
+        for object in self.list.iter() {
+            if object.sample_collision(r, t_min, closest_so_far, &mut temp_rec, sampler) {
+                hit_anything = true;
+                closest_so_far = temp_rec.t;
+                *rec = temp_rec.clone();
             }
         }
         hit_anything
     }
+
+    /// Density of picking one object uniformly and then a direction towards it.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        if self.list.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .list
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.list.len() as f64
+    }
+
+    /// Picks one object uniformly, so an object that cannot be sampled wastes
+    /// the samples that pick it.
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        if self.list.is_empty() {
+            return None;
+        }
+        let index: usize = sampler.gen_range(0, self.list.len());
+        self.list[index].random(origin, sampler)
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f64, t_max: f64, sampler: &mut Sampler) -> f64 {
+        let mut transmittance: f64 = 1.0;
+        for object in self.list.iter() {
+            transmittance *= object.transmittance(r, t_min, t_max, sampler);
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::bvh::BvhNode;
+    use super::sphere::Sphere;
     use super::*;
 
     #[test]
@@ -80,6 +261,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_hittable() {
         let horizontal: Vec3 = Vec3::new(4.0, 0.0, 0.0);
         let lower_left_corner: Vec3 = Vec3::new(-2.0, -1.0, -1.0);
@@ -119,4 +301,36 @@ mod test {
 
         assert!(true);
     }
+
+    #[test]
+    fn test_nested_hittables() {
+        let lambert = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let shared: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -3.0), 0.5, lambert.clone()));
+
+        let mut inner = HittableList::new();
+        inner.push(Sphere::new(Vec3::new(0.0, 0.0, -2.0), 0.5, lambert.clone()));
+        inner.push_shared(Arc::clone(&shared));
+
+        let mut world = HittableList::new();
+        world.push(BvhNode::new(inner));
+        world.push_shared(shared);
+        world.push(Sphere::new(Vec3::new(0.0, 0.0, -5.0), 0.5, lambert));
+        assert_eq!(world.size(), 3);
+
+        let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(world.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.5);
+
+        let mut bbox = Aabb::empty();
+        assert!(world.bounding_box(&mut bbox));
+        assert_eq!(bbox.min, Vec3::new(-0.5, -0.5, -5.5));
+        assert_eq!(bbox.max, Vec3::new(0.5, 0.5, -1.5));
+
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let mut sampler = Sampler::new(0);
+        assert!(world.random(&origin, &mut sampler).is_some());
+        assert!(HittableList::new().random(&origin, &mut sampler).is_none());
+    }
 }
@@ -0,0 +1,183 @@
+use super::sphere;
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Sphere whose centre moves along a path of keyframes, for motion blur.
+///
+/// The centre is interpolated linearly between consecutive keyframes and
+/// held at the first or last one outside their time range.
+#[derive(Clone)]
+pub struct MovingSphere {
+    /// `(time, center)` pairs sorted by time.
+    keyframes: Vec<(f64, Vec3)>,
+    pub radius: f64,
+    pub material: Material,
+}
+
+impl MovingSphere {
+    /// Sphere moving in a straight line from `center0` at `time0` to `center1`
+    /// at `time1`.
+    pub fn new(
+        center0: Vec3,
+        time0: f64,
+        center1: Vec3,
+        time1: f64,
+        radius: f64,
+        material: Material,
+    ) -> MovingSphere {
+        MovingSphere::keyframed(vec![(time0, center0), (time1, center1)], radius, material)
+    }
+
+    pub fn keyframed(
+        mut keyframes: Vec<(f64, Vec3)>,
+        radius: f64,
+        material: Material,
+    ) -> MovingSphere {
+        assert!(!keyframes.is_empty(), "a moving sphere needs a keyframe");
+        assert!(
+            keyframes.iter().all(|k| k.0.is_finite()),
+            "keyframe times of a moving sphere must be finite"
+        );
+        keyframes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        MovingSphere {
+            keyframes,
+            radius,
+            material,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[(f64, Vec3)] {
+        &self.keyframes
+    }
+
+    pub fn center(&self, time: f64) -> Vec3 {
+        let next: usize = self.keyframes.partition_point(|k| k.0 <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+        let (t0, c0) = self.keyframes[next - 1];
+        let (t1, c1) = self.keyframes[next];
+        c0 + (c1 - c0) * ((time - t0) / (t1 - t0))
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let center: Vec3 = self.center(r.time());
+        match sphere::intersect(&center, self.radius, r, t_min, t_max) {
+            Some(t) => {
+                sphere::record_hit(&center, self.radius, &self.material, r, t, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Encloses the sphere at every keyframe, and so the whole path between
+    /// them.
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self
+            .keyframes
+            .iter()
+            .map(|k| sphere::bounding_box(&k.1, self.radius))
+            .fold(Aabb::empty(), |a, b| Aabb::surrounding_box(&a, &b));
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::objects::bvh::BvhNode;
+    use crate::objects::camera::Camera;
+    use crate::structs::sampler::Sampler;
+
+    fn material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn test_center() {
+        let sphere = MovingSphere::keyframed(
+            vec![
+                (1.0, Vec3::new(2.0, 0.0, 0.0)),
+                (0.0, Vec3::new(0.0, 0.0, 0.0)),
+                (2.0, Vec3::new(2.0, 4.0, 0.0)),
+            ],
+            0.5,
+            material(),
+        );
+        assert_eq!(sphere.keyframes()[0].0, 0.0);
+        assert_eq!(sphere.center(-1.0), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(sphere.center(0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(sphere.center(1.0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(sphere.center(1.25), Vec3::new(2.0, 1.0, 0.0));
+        assert_eq!(sphere.center(3.0), Vec3::new(2.0, 4.0, 0.0));
+
+        let mut bbox = Aabb::empty();
+        assert!(sphere.bounding_box(&mut bbox));
+        assert_eq!(bbox.min, Vec3::new(-0.5, -0.5, -0.5));
+        assert_eq!(bbox.max, Vec3::new(2.5, 4.5, 0.5));
+    }
+
+    #[test]
+    fn test_hit_at_time() {
+        let sphere = MovingSphere::new(
+            Vec3::new(0.0, 0.0, -2.0),
+            0.0,
+            Vec3::new(4.0, 0.0, -2.0),
+            1.0,
+            0.5,
+            material(),
+        );
+        let mut list = HittableList::new();
+        list.push(sphere);
+        let world = BvhNode::new(list);
+        let mut rec = HitRecord::new();
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let direction = Vec3::new(0.0, 0.0, -1.0);
+        assert!(world.hit(&Ray::new(origin, direction), 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.5);
+        let late = Ray::with_time(origin, direction, 1.0);
+        assert!(!world.hit(&late, 0.0, f64::MAX, &mut rec));
+        let offset = Ray::with_time(Vec3::new(4.0, 0.0, 0.0), direction, 1.0);
+        assert!(world.hit(&offset, 0.0, f64::MAX, &mut rec));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be finite")]
+    fn test_nan_keyframe() {
+        let center = Vec3::new(0.0, 0.0, 0.0);
+        MovingSphere::keyframed(vec![(0.0, center), (f64::NAN, center)], 1.0, material());
+    }
+
+    #[test]
+    fn test_camera_shutter() {
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let mut sampler = Sampler::new(0);
+        assert_eq!(camera.get_ray(0.5, 0.5, &mut sampler).time(), 0.0);
+
+        let camera = camera.shutter(0.25, 0.75);
+        let times: Vec<f64> = (0..1000)
+            .map(|_| camera.get_ray(0.5, 0.5, &mut sampler).time())
+            .collect();
+        assert!(times.iter().all(|t| (0.25..=0.75).contains(t)));
+        let mean: f64 = times.iter().sum::<f64>() / times.len() as f64;
+        assert!((mean - 0.5).abs() < 0.02);
+    }
+}
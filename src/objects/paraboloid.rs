@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use super::quadric::{phi, solve_quadratic, sweep};
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Paraboloid around the y axis with its vertex at the origin, opening
+/// upwards to `radius` at `y_max` and cut off below `y_min`. The normal faces
+/// away from the axis.
+///
+/// `u` runs from 0 to 1 around the sweep and `v` from 0 at `y_min` to 1 at
+/// `y_max`.
+#[derive(Clone)]
+pub struct Paraboloid {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    /// Sweep in radians.
+    pub phi_max: f64,
+    pub material: Material,
+}
+
+impl Paraboloid {
+    pub fn new(radius: f64, y_min: f64, y_max: f64, material: Material) -> Paraboloid {
+        Paraboloid {
+            radius,
+            y_min: y_min.clamp(0.0, y_max),
+            y_max,
+            phi_max: 2.0 * PI,
+            material,
+        }
+    }
+
+    /// Cuts the paraboloid down to a sector of `degrees`.
+    pub fn phi_max(mut self, degrees: f64) -> Paraboloid {
+        self.phi_max = sweep(degrees);
+        self
+    }
+}
+
+impl Hittable for Paraboloid {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (o, d) = (r.origin(), r.direction());
+        // x^2 + z^2 = k y
+        let k: f64 = self.radius * self.radius / self.y_max;
+        let a: f64 = d.x() * d.x() + d.z() * d.z();
+        let b: f64 = 2.0 * (o.x() * d.x() + o.z() * d.z()) - k * d.y();
+        let c: f64 = o.x() * o.x() + o.z() * o.z() - k * o.y();
+        let (t0, t1) = match solve_quadratic(a, b, c) {
+            Some(roots) => roots,
+            None => return false,
+        };
+        for t in [t0, t1].iter() {
+            if *t <= t_min || *t >= t_max {
+                continue;
+            }
+            let p: Vec3 = r.point_at_parameter(*t);
+            let phi: f64 = phi(&p);
+            if p.y() < self.y_min || p.y() > self.y_max || phi > self.phi_max {
+                continue;
+            }
+            rec.t = *t;
+            rec.p = p;
+            rec.normal = Vec3::unit_vector(&Vec3::new(2.0 * p.x(), -k, 2.0 * p.z()));
+            rec.geometric_normal = rec.normal;
+            rec.u = phi / self.phi_max;
+            rec.v = (p.y() - self.y_min) / (self.y_max - self.y_min);
+            rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+            rec.material = Cow::Borrowed(&self.material);
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(
+            Vec3::new(-self.radius, self.y_min, -self.radius),
+            Vec3::new(self.radius, self.y_max, self.radius),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn test_hit() {
+        // x^2 + z^2 = 2 y
+        let paraboloid = Paraboloid::new(
+            2.0,
+            0.0,
+            2.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(paraboloid.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 4.0).abs() < 1e-12);
+        assert!((rec.v - 0.25).abs() < 1e-12);
+        let expected = Vec3::unit_vector(&Vec3::new(1.0, -1.0, 0.0));
+        assert!((rec.normal - expected).length() < 1e-12);
+
+        // Straight down the axis onto the vertex.
+        let r = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(paraboloid.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 5.0).abs() < 1e-12);
+
+        // Cutting the bottom off opens a hole around the vertex.
+        let cut = Paraboloid::new(2.0, 1.0, 2.0, paraboloid.material.clone()).phi_max(180.0);
+        assert!(!cut.hit(&r, 0.0, f64::MAX, &mut rec));
+        let r = Ray::new(Vec3::new(0.0, 5.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!cut.hit(&r, 0.0, f64::MAX, &mut rec));
+        let r = Ray::new(Vec3::new(0.0, 5.0, -1.5), Vec3::new(0.0, -1.0, 0.0));
+        assert!(cut.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.p.y() - 1.125).abs() < 1e-12);
+    }
+}
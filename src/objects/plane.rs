@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::onb::Onb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Infinite plane through `point`, facing `normal`.
+///
+/// Surface coordinates are distances from `point` along two directions in the
+/// plane, so textures repeat once per unit. It has no bounding box; a
+/// `BvhNode` keeps it outside the hierarchy and tests it for every ray.
+#[derive(Clone)]
+pub struct Plane {
+    pub point: Vec3,
+    pub material: Material,
+    basis: Onb,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Material) -> Plane {
+        Plane {
+            point,
+            material,
+            basis: Onb::from_w(&normal),
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.basis.w
+    }
+}
+
+impl Hittable for Plane {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let denom: f64 = Vec3::dot(&self.basis.w, &r.direction());
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t: f64 = Vec3::dot(&self.basis.w, &(self.point - r.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+        rec.t = t;
+        rec.p = r.point_at_parameter(t);
+        rec.normal = self.basis.w;
+        rec.geometric_normal = self.basis.w;
+        let planar: Vec3 = rec.p - self.point;
+        rec.u = Vec3::dot(&planar, &self.basis.u);
+        rec.v = Vec3::dot(&planar, &self.basis.v);
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.material);
+        true
+    }
+
+    fn bounding_box(&self, _output_box: &mut Aabb) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn test_hit() {
+        let plane = Plane::new(
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(3.0, 1.0, -2.0), Vec3::new(0.0, -1.0, -1.0));
+        assert!(plane.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.p, Vec3::new(3.0, -1.0, -4.0));
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        let (u, v) = (rec.u, rec.v);
+        assert!((u * u + v * v - 25.0).abs() < 1e-12);
+
+        let parallel = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!plane.hit(&parallel, 0.0, f64::MAX, &mut rec));
+        let away = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(!plane.hit(&away, 0.0, f64::MAX, &mut rec));
+        assert!(!plane.bounding_box(&mut Aabb::empty()));
+    }
+}
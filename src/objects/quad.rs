@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+
+use rand::Rng;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Padding that keeps the box of an axis-aligned quad from being flat.
+const BOX_PADDING: f64 = 0.0001;
+
+/// Parallelogram with corners `q`, `q + u`, `q + u + v` and `q + v`, facing
+/// the side of `u x v`. Surface coordinates run from 0 to 1 along `u` and `v`.
+#[derive(Clone)]
+pub struct Quad {
+    pub q: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Material,
+    normal: Vec3,
+    /// `n / (n . n)` for the unnormalized normal `n`, which turns cross
+    /// products with `u` and `v` into surface coordinates.
+    w: Vec3,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, material: Material) -> Quad {
+        let n: Vec3 = Vec3::cross(&u, &v);
+        Quad {
+            q,
+            u,
+            v,
+            material,
+            normal: Vec3::unit_vector(&n),
+            w: n / n.length_squared(),
+            area: n.length(),
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+}
+
+impl Hittable for Quad {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let denom: f64 = Vec3::dot(&self.normal, &r.direction());
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+        let t: f64 = Vec3::dot(&self.normal, &(self.q - r.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+        let p: Vec3 = r.point_at_parameter(t);
+        let planar: Vec3 = p - self.q;
+        let alpha: f64 = Vec3::dot(&self.w, &Vec3::cross(&planar, &self.v));
+        let beta: f64 = Vec3::dot(&self.w, &Vec3::cross(&self.u, &planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+        rec.t = t;
+        rec.p = p;
+        rec.normal = self.normal;
+        rec.geometric_normal = self.normal;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.material);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let mut bbox = Aabb::empty();
+        for corner in [
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ]
+        .iter()
+        {
+            bbox = Aabb::surrounding_box(&bbox, &Aabb::new(*corner, *corner));
+        }
+        let pad = Vec3::new(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+        *output_box = Aabb::new(bbox.min - pad, bbox.max + pad);
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit(&Ray::new(*origin, *direction), 0.001, f64::MAX, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared: f64 = rec.t * rec.t * direction.length_squared();
+        let cosine: f64 = (Vec3::dot(direction, &self.normal) / direction.length()).abs();
+        // Seen edge on, or degenerate, the quad covers no solid angle.
+        if cosine * self.area < 1e-12 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        Some(self.q + self.u * sampler.gen::<f64>() + self.v * sampler.gen::<f64>() - *origin)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn quad() -> Quad {
+        Quad::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let quad = quad();
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(2.0, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(quad.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!((rec.u, rec.v), (0.75, 0.5));
+
+        // Inside the bounding rectangle but outside the slanted parallelogram.
+        let r = Ray::new(Vec3::new(0.2, 0.9, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(!quad.hit(&r, 0.0, f64::MAX, &mut rec));
+        let r = Ray::new(Vec3::new(1.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!quad.hit(&r, 0.0, f64::MAX, &mut rec));
+
+        let mut bbox = Aabb::empty();
+        assert!(quad.bounding_box(&mut bbox));
+        assert!(bbox.min.z() < -1.0 && bbox.max.z() > -1.0);
+        assert_eq!(
+            (bbox.min.x(), bbox.max.y()),
+            (-BOX_PADDING, 1.0 + BOX_PADDING)
+        );
+    }
+
+    #[test]
+    fn test_sampling() {
+        let quad = quad();
+        let origin = Vec3::new(1.5, 0.5, 0.0);
+        assert_eq!(quad.area(), 2.0);
+        assert!((quad.pdf_value(&origin, &Vec3::new(0.0, 0.0, -1.0)) - 0.5).abs() < 1e-12);
+        let mut sampler = Sampler::new(0);
+        for _ in 0..100 {
+            let direction: Vec3 = quad.random(&origin, &mut sampler).unwrap();
+            assert!((direction.z() + 1.0).abs() < 1e-12);
+            assert!(quad.pdf_value(&origin, &direction) > 0.0);
+        }
+
+        let sliver = Quad::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 1e-13, 0.0),
+            quad.material.clone(),
+        );
+        let origin = Vec3::new(1.0, 0.0, 0.0);
+        assert_eq!(sliver.pdf_value(&origin, &Vec3::new(0.0, 0.0, -1.0)), 0.0);
+    }
+}
@@ -0,0 +1,198 @@
+//! Root finding and angle helpers shared by the analytic shapes.
+//!
+//! All of them are built around the y axis, with the azimuth `phi` measured
+//! counter-clockwise from +x when looking down from +y. A shape with a
+//! `phi_max` below 360 degrees is cut open along that angle.
+
+use std::f64::consts::PI;
+
+use crate::structs::vec3::Vec3;
+
+/// Real roots of `a x^2 + b x + c` in ascending order. Uses the form that
+/// avoids cancellation between `b` and the square root, and falls back to the
+/// linear equation when `a` is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t: f64 = -c / b;
+        return Some((t, t));
+    }
+    let discr: f64 = b * b - 4.0 * a * c;
+    if discr < 0.0 {
+        return None;
+    }
+    let root: f64 = discr.sqrt();
+    let q: f64 = if b < 0.0 {
+        -0.5 * (b - root)
+    } else {
+        -0.5 * (b + root)
+    };
+    let t0: f64 = q / a;
+    let t1: f64 = if q != 0.0 { c / q } else { t0 };
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Largest real root of the monic cubic `x^3 + b x^2 + c x + d`.
+fn largest_cubic_root(b: f64, c: f64, d: f64) -> f64 {
+    let q: f64 = (b * b - 3.0 * c) / 9.0;
+    let r: f64 = (2.0 * b * b * b - 9.0 * b * c + 27.0 * d) / 54.0;
+    if r * r < q * q * q {
+        // Three real roots at angles (theta + 2 pi k) / 3. For k = 1 the
+        // cosine is the most negative, which gives the largest root.
+        let theta: f64 = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        -2.0 * q.sqrt() * ((theta + 2.0 * PI) / 3.0).cos() - b / 3.0
+    } else {
+        let a: f64 = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let b_term: f64 = if a != 0.0 { q / a } else { 0.0 };
+        a + b_term - b / 3.0
+    }
+}
+
+/// Real roots of `a x^4 + b x^3 + c x^2 + d x + e` in ascending order.
+///
+/// Solved with Ferrari's method through the resolvent cubic, then every root
+/// is polished with a few Newton steps on the original polynomial, which
+/// recovers the precision the closed form loses.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return Vec::new();
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    // Depressed quartic y^4 + p y^2 + q y + r with x = y - b / 4.
+    let shift: f64 = b / 4.0;
+    let p: f64 = c - 6.0 * shift * shift;
+    let q: f64 = d - 2.0 * c * shift + 8.0 * shift * shift * shift;
+    let r: f64 = e - d * shift + c * shift * shift - 3.0 * shift * shift * shift * shift;
+
+    let mut roots: Vec<f64> = Vec::with_capacity(4);
+    if q.abs() < 1e-12 * (1.0 + p.abs() + r.abs()) {
+        // Biquadratic: a quadratic in y^2.
+        if let Some((z0, z1)) = solve_quadratic(1.0, p, r) {
+            for z in [z0, z1].iter() {
+                if *z >= 0.0 {
+                    roots.push(z.sqrt());
+                    roots.push(-z.sqrt());
+                }
+            }
+        }
+    } else {
+        // A positive root m of 8m^3 + 8p m^2 + (2p^2 - 8r) m - q^2 splits the
+        // quartic into two quadratics in y.
+        let m: f64 = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m > 0.0 {
+            let s: f64 = (2.0 * m).sqrt();
+            for sign in [1.0, -1.0].iter() {
+                let constant: f64 = p / 2.0 + m - sign * q / (2.0 * s);
+                if let Some((y0, y1)) = solve_quadratic(1.0, sign * s, constant) {
+                    roots.push(y0);
+                    roots.push(y1);
+                }
+            }
+        }
+    }
+
+    let polynomial = |x: f64| -> (f64, f64) {
+        let value: f64 = (((x + b) * x + c) * x + d) * x + e;
+        let slope: f64 = ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+        (value, slope)
+    };
+    let mut roots: Vec<f64> = roots
+        .into_iter()
+        .map(|y| {
+            let mut x: f64 = y - shift;
+            for _ in 0..4 {
+                let (value, slope) = polynomial(x);
+                if slope == 0.0 {
+                    break;
+                }
+                x -= value / slope;
+            }
+            x
+        })
+        .filter(|x| x.is_finite())
+        .collect();
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+/// Azimuth of `p` around the y axis, in `[0, 2 pi)`.
+pub fn phi(p: &Vec3) -> f64 {
+    let phi: f64 = (-p.z()).atan2(p.x());
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// `degrees` as a sweep angle in radians, within `(0, 2 pi]`. Panics unless
+/// `degrees` is positive, since surface coordinates are divided by it.
+pub fn sweep(degrees: f64) -> f64 {
+    assert!(
+        degrees > 0.0,
+        "sweep angle must be positive, got {}",
+        degrees
+    );
+    degrees.min(360.0).to_radians()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solve_quadratic() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(0.0, 2.0, -4.0), Some((2.0, 2.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+        // The naive formula loses the small root to cancellation here.
+        let (large, small) = solve_quadratic(1.0, 1e8, 1.0).unwrap();
+        assert!((large + 1e8).abs() < 1e-6);
+        assert!((small + 1e-8).abs() < 1e-20);
+    }
+
+    #[test]
+    fn test_largest_cubic_root() {
+        // (x - 1)(x - 2)(x - 3) has three real roots, (x - 2)(x^2 + 1) one.
+        assert!((largest_cubic_root(-6.0, 11.0, -6.0) - 3.0).abs() < 1e-12);
+        assert!((largest_cubic_root(-2.0, 1.0, -2.0) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solve_quartic() {
+        // (x - 1)(x + 2)(x - 3)(x - 0.5)
+        let roots = solve_quartic(1.0, -2.5, -4.0, 8.5, -3.0);
+        let expected = [-2.0, 0.5, 1.0, 3.0];
+        assert_eq!(roots.len(), 4);
+        for (root, e) in roots.iter().zip(expected.iter()) {
+            assert!((root - e).abs() < 1e-12, "{:?}", roots);
+        }
+        // Biquadratic: (x^2 - 1)(x^2 - 4).
+        let roots = solve_quartic(2.0, 0.0, -10.0, 0.0, 8.0);
+        assert_eq!(roots, vec![-2.0, -1.0, 1.0, 2.0]);
+        // x^4 + 1 has no real roots, (x^2 + 1)(x - 2)(x - 5) has two.
+        assert!(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0).is_empty());
+        let roots = solve_quartic(1.0, -7.0, 11.0, -7.0, 10.0);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] - 2.0).abs() < 1e-12 && (roots[1] - 5.0).abs() < 1e-12);
+        // Roots that overflow while polishing are dropped instead of panicking.
+        let roots = solve_quartic(1.0, -1e100, 0.0, 0.0, 0.0);
+        assert!(roots.iter().all(|x| x.is_finite()), "{:?}", roots);
+    }
+
+    #[test]
+    fn test_phi() {
+        assert_eq!(phi(&Vec3::new(1.0, 5.0, 0.0)), 0.0);
+        assert!((phi(&Vec3::new(0.0, 0.0, -1.0)) - PI / 2.0).abs() < 1e-12);
+        assert!((phi(&Vec3::new(0.0, 0.0, 1.0)) - 1.5 * PI).abs() < 1e-12);
+        assert_eq!(sweep(400.0), 2.0 * PI);
+    }
+
+    #[test]
+    #[should_panic(expected = "sweep angle must be positive")]
+    fn test_zero_sweep() {
+        sweep(0.0);
+    }
+}
@@ -0,0 +1,291 @@
+use std::borrow::Cow;
+
+use rand::Rng;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Padding that keeps the box of a rectangle from being flat.
+const BOX_PADDING: f64 = 0.0001;
+
+/// Rectangle `[a0, a1] x [b0, b1]` in the plane where axis `k_axis` equals
+/// `k`, shared by the three axis-aligned rectangle types.
+#[derive(Clone)]
+struct AxisRect {
+    a_axis: usize,
+    b_axis: usize,
+    k_axis: usize,
+    a0: f64,
+    a1: f64,
+    b0: f64,
+    b1: f64,
+    k: f64,
+    flipped: bool,
+    material: Material,
+}
+
+impl AxisRect {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        axes: (usize, usize, usize),
+        a0: f64,
+        a1: f64,
+        b0: f64,
+        b1: f64,
+        k: f64,
+        material: Material,
+    ) -> AxisRect {
+        AxisRect {
+            a_axis: axes.0,
+            b_axis: axes.1,
+            k_axis: axes.2,
+            a0: a0.min(a1),
+            a1: a0.max(a1),
+            b0: b0.min(b1),
+            b1: b0.max(b1),
+            k,
+            flipped: false,
+            material,
+        }
+    }
+
+    fn point(&self, a: f64, b: f64, k: f64) -> Vec3 {
+        let mut p: [f64; 3] = [0.0; 3];
+        p[self.a_axis] = a;
+        p[self.b_axis] = b;
+        p[self.k_axis] = k;
+        Vec3::new(p[0], p[1], p[2])
+    }
+
+    fn normal(&self) -> Vec3 {
+        self.point(0.0, 0.0, if self.flipped { -1.0 } else { 1.0 })
+    }
+
+    fn area(&self) -> f64 {
+        (self.a1 - self.a0) * (self.b1 - self.b0)
+    }
+
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let (origin, direction) = (r.origin(), r.direction());
+        let t: f64 = (self.k - origin[self.k_axis]) / direction[self.k_axis];
+        // Also rejects the NaN of rays parallel to the plane.
+        if !(t > t_min && t < t_max) {
+            return false;
+        }
+        let a: f64 = origin[self.a_axis] + t * direction[self.a_axis];
+        let b: f64 = origin[self.b_axis] + t * direction[self.b_axis];
+        if a < self.a0 || a > self.a1 || b < self.b0 || b > self.b1 {
+            return false;
+        }
+        rec.t = t;
+        rec.p = r.point_at_parameter(t);
+        rec.normal = self.normal();
+        rec.geometric_normal = rec.normal;
+        rec.u = (a - self.a0) / (self.a1 - self.a0);
+        rec.v = (b - self.b0) / (self.b1 - self.b0);
+        rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+        rec.material = Cow::Borrowed(&self.material);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(
+            self.point(self.a0, self.b0, self.k - BOX_PADDING),
+            self.point(self.a1, self.b1, self.k + BOX_PADDING),
+        )
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit(&Ray::new(*origin, *direction), 0.001, f64::MAX, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared: f64 = rec.t * rec.t * direction.length_squared();
+        let cosine: f64 = (direction[self.k_axis] / direction.length()).abs();
+        // Seen edge on, or degenerate, the rectangle covers no solid angle.
+        if cosine * self.area() < 1e-12 {
+            return 0.0;
+        }
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Vec3 {
+        let a: f64 = self.a0 + (self.a1 - self.a0) * sampler.gen::<f64>();
+        let b: f64 = self.b0 + (self.b1 - self.b0) * sampler.gen::<f64>();
+        self.point(a, b, self.k) - *origin
+    }
+}
+
+/// Rectangle `[x0, x1] x [y0, y1]` at `z = k`, facing +z unless flipped.
+/// `u` runs along x and `v` along y.
+#[derive(Clone)]
+pub struct XYRect(AxisRect);
+
+/// Rectangle `[x0, x1] x [z0, z1]` at `y = k`, facing +y unless flipped.
+/// `u` runs along x and `v` along z.
+#[derive(Clone)]
+pub struct XZRect(AxisRect);
+
+/// Rectangle `[y0, y1] x [z0, z1]` at `x = k`, facing +x unless flipped.
+/// `u` runs along y and `v` along z.
+#[derive(Clone)]
+pub struct YZRect(AxisRect);
+
+impl XYRect {
+    pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, material: Material) -> XYRect {
+        XYRect(AxisRect::new((0, 1, 2), x0, x1, y0, y1, k, material))
+    }
+
+    /// Turns the rectangle to face -z.
+    pub fn flip(mut self) -> XYRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl XZRect {
+    pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, material: Material) -> XZRect {
+        XZRect(AxisRect::new((0, 2, 1), x0, x1, z0, z1, k, material))
+    }
+
+    /// Turns the rectangle to face -y.
+    pub fn flip(mut self) -> XZRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl YZRect {
+    pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, material: Material) -> YZRect {
+        YZRect(AxisRect::new((1, 2, 0), y0, y1, z0, z1, k, material))
+    }
+
+    /// Turns the rectangle to face -x.
+    pub fn flip(mut self) -> YZRect {
+        self.0.flipped = !self.0.flipped;
+        self
+    }
+}
+
+impl Hittable for XYRect {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        self.0.hit(r, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.0.bounding_box();
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.0.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        Some(self.0.random(origin, sampler))
+    }
+}
+
+impl Hittable for XZRect {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        self.0.hit(r, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.0.bounding_box();
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.0.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        Some(self.0.random(origin, sampler))
+    }
+}
+
+impl Hittable for YZRect {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        self.0.hit(r, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.0.bounding_box();
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.0.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        Some(self.0.random(origin, sampler))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn material() -> Material {
+        Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+    }
+
+    #[test]
+    fn test_hit() {
+        let mut rec = HitRecord::new();
+        let xz = XZRect::new(0.0, 2.0, -1.0, 1.0, 1.0, material());
+        let down = Ray::new(Vec3::new(1.5, 3.0, 0.5), Vec3::new(0.0, -1.0, 0.0));
+        assert!(xz.hit(&down, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!((rec.u, rec.v), (0.75, 0.75));
+        assert!(!xz.hit(&down, 0.0, 1.5, &mut rec));
+        let flat = Ray::new(Vec3::new(1.5, 1.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!xz.hit(&flat, 0.0, f64::MAX, &mut rec));
+        let outside = Ray::new(Vec3::new(2.5, 3.0, 0.5), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!xz.hit(&outside, 0.0, f64::MAX, &mut rec));
+
+        let xy = XYRect::new(1.0, -1.0, -1.0, 1.0, -2.0, material()).flip();
+        let r = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(xy.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!((rec.u, rec.v), (0.5, 0.75));
+
+        let yz = YZRect::new(0.0, 1.0, 0.0, 1.0, 3.0, material());
+        let r = Ray::new(Vec3::new(0.0, 0.25, 0.5), Vec3::new(1.0, 0.0, 0.0));
+        assert!(yz.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.p, Vec3::new(3.0, 0.25, 0.5));
+        assert_eq!(rec.normal, Vec3::new(1.0, 0.0, 0.0));
+
+        let mut bbox = Aabb::empty();
+        assert!(yz.bounding_box(&mut bbox));
+        assert!(bbox.max.x() > bbox.min.x());
+        assert_eq!((bbox.min.y(), bbox.max.z()), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_sampling() {
+        let light = XZRect::new(-1.0, 1.0, -1.0, 1.0, 2.0, material()).flip();
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        // Straight up the rectangle is at distance 2 and faces the origin.
+        assert!((light.pdf_value(&origin, &Vec3::new(0.0, 1.0, 0.0)) - 1.0).abs() < 1e-12);
+        assert_eq!(light.pdf_value(&origin, &Vec3::new(0.0, -1.0, 0.0)), 0.0);
+        let mut sampler = Sampler::new(0);
+        for _ in 0..100 {
+            let direction: Vec3 = light.random(&origin, &mut sampler).unwrap();
+            assert_eq!(direction.y(), 2.0);
+            assert!(direction.x().abs() <= 1.0 && direction.z().abs() <= 1.0);
+            assert!(light.pdf_value(&origin, &direction) > 0.0);
+        }
+
+        let line = XZRect::new(0.0, 0.0, -1.0, 1.0, 2.0, material());
+        assert_eq!(line.pdf_value(&origin, &Vec3::new(0.0, 1.0, 0.0)), 0.0);
+    }
+}
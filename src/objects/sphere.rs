@@ -1,8 +1,17 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use rand::Rng;
+
 use super::*;
 use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::onb::Onb;
 use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
 use crate::structs::vec3::Vec3;
 
+#[derive(Clone)]
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f64,
@@ -14,7 +23,7 @@ impl Sphere {
         Sphere {
             center: c,
             radius: r,
-            material: material,
+            material,
         }
     }
 
@@ -27,32 +36,169 @@ impl Sphere {
     }
 }
 
+/// Longitude-latitude coordinates of a point `p` on the unit sphere. `u` runs
+/// once around the y axis starting at -x, `v` goes from 0 at -y to 1 at +y.
+pub fn sphere_uv(p: &Vec3) -> (f64, f64) {
+    let theta: f64 = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi: f64 = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Nearest `t` in `(t_min, t_max)` at which `r` meets the sphere, trying the
+/// far root for rays that start inside.
+pub(crate) fn intersect(
+    center: &Vec3,
+    radius: f64,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<f64> {
+    let oc: Vec3 = r.origin() - *center;
+    let a: f64 = Vec3::dot(&r.direction(), &r.direction());
+    let b: f64 = Vec3::dot(&oc, &r.direction());
+    let c: f64 = Vec3::dot(&oc, &oc) - radius * radius;
+    let discr: f64 = b * b - a * c;
+    if discr > 0.0 {
+        let temp: f64 = (-b - discr.sqrt()) / a;
+        if temp < t_max && temp > t_min {
+            return Some(temp);
+        }
+        let temp: f64 = (-b + discr.sqrt()) / a;
+        if temp < t_max && temp > t_min {
+            return Some(temp);
+        }
+    }
+    None
+}
+
+/// Fills `rec` for a hit at `t` on the sphere. A negative `radius` turns the
+/// normals inwards.
+pub(crate) fn record_hit<'a>(
+    center: &Vec3,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t: f64,
+    rec: &mut HitRecord<'a>,
+) {
+    rec.t = t;
+    rec.p = r.point_at_parameter(t);
+    rec.normal = (rec.p - *center) / radius;
+    rec.geometric_normal = rec.normal;
+    let (u, v) = sphere_uv(&((rec.p - *center) / radius.abs()));
+    rec.u = u;
+    rec.v = v;
+    rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+    rec.material = Cow::Borrowed(material);
+}
+
+/// Box around a sphere of radius `radius`, which may be negative.
+pub(crate) fn bounding_box(center: &Vec3, radius: f64) -> Aabb {
+    let r: f64 = radius.abs();
+    Aabb::new(*center - Vec3::new(r, r, r), *center + Vec3::new(r, r, r))
+}
+
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
-        let oc: Vec3 = r.origin() - self.center;
-        let a: f64 = Vec3::dot(&r.direction(), &r.direction());
-        let b: f64 = Vec3::dot(&oc, &r.direction());
-        let c: f64 = Vec3::dot(&oc, &oc) - self.radius * self.radius;
-        let discr: f64 = b * b - a * c;
-        if discr > 0.0 {
-            let temp: f64 = (-b - discr.sqrt()) / a;
-            if temp < t_max && temp > t_min {
-                rec.t = temp;
-                rec.p = r.point_at_parameter(rec.t);
-                rec.normal = (rec.p - self.center) / self.radius;
-                rec.material = self.material;
-                return true;
-            }
-        } else {
-            let temp = (-b + discr.sqrt()) / a;
-            if temp < t_max && temp > t_min {
-                rec.t = temp;
-                rec.p = r.point_at_parameter(rec.t);
-                rec.normal = (rec.p - self.center) / self.radius;
-                rec.material = self.material;
-                return true;
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        match intersect(&self.center, self.radius, r, t_min, t_max) {
+            Some(t) => {
+                record_hit(&self.center, self.radius, &self.material, r, t, rec);
+                true
             }
+            None => false,
         }
-        return false;
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = bounding_box(&self.center, self.radius);
+        true
+    }
+
+    /// Uniform over the cone of directions that see the sphere, or over all
+    /// directions from inside it.
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit(&Ray::new(*origin, *direction), 0.001, f64::MAX, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared: f64 = (self.center - *origin).length_squared();
+        let radius_squared: f64 = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 1.0 / (4.0 * PI);
+        }
+        let cos_theta_max: f64 = (1.0 - radius_squared / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        let direction: Vec3 = self.center - *origin;
+        let distance_squared: f64 = direction.length_squared();
+        let radius_squared: f64 = self.radius * self.radius;
+        let phi: f64 = 2.0 * PI * sampler.gen::<f64>();
+        let r2: f64 = sampler.gen::<f64>();
+        if distance_squared <= radius_squared {
+            let z: f64 = 1.0 - 2.0 * r2;
+            let sin_theta: f64 = (1.0 - z * z).max(0.0).sqrt();
+            return Some(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z));
+        }
+        let cos_theta_max: f64 = (1.0 - radius_squared / distance_squared).sqrt();
+        let z: f64 = 1.0 + r2 * (cos_theta_max - 1.0);
+        let sin_theta: f64 = (1.0 - z * z).max(0.0).sqrt();
+        Some(Onb::from_w(&direction).local(&Vec3::new(
+            phi.cos() * sin_theta,
+            phi.sin() * sin_theta,
+            z,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    #[test]
+    fn test_hit_from_inside() {
+        let sphere = Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            2.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let mut rec = HitRecord::new();
+        let outside = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(sphere.hit(&outside, 0.001, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 3.0);
+        assert!(sphere.hit(&outside, 3.5, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 7.0);
+
+        let inside = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(sphere.hit(&inside, 0.001, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_uv() {
+        let close = |p: Vec3, uv: (f64, f64)| {
+            let (u, v) = sphere_uv(&p);
+            assert!(
+                (u - uv.0).abs() < 1e-12 && (v - uv.1).abs() < 1e-12,
+                "{:?}",
+                p
+            );
+        };
+        close(Vec3::new(1.0, 0.0, 0.0), (0.5, 0.5));
+        close(Vec3::new(0.0, 1.0, 0.0), (0.5, 1.0));
+        close(Vec3::new(0.0, -1.0, 0.0), (0.5, 0.0));
+        close(Vec3::new(0.0, 0.0, 1.0), (0.25, 0.5));
+        close(Vec3::new(0.0, 0.0, -1.0), (0.75, 0.5));
+
+        // Hollow spheres with a negative radius get the same coordinates.
+        let material = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), -0.5, material);
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(sphere.hit(&r, 0.001, f64::MAX, &mut rec));
+        assert!((rec.u - 0.25).abs() < 1e-12 && (rec.v - 0.5).abs() < 1e-12);
     }
 }
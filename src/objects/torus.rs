@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+use std::f64::consts::PI;
+
+use super::quadric::{phi, solve_quartic, sweep};
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Torus around the y axis, with a tube of radius `minor_radius` whose center
+/// circles the origin at `major_radius` in the xz plane. The normal faces out
+/// of the tube.
+///
+/// `u` runs from 0 to 1 around the sweep and `v` from 0 to 1 around the tube,
+/// starting at its outer equator and going over the top.
+#[derive(Clone)]
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    /// Sweep in radians.
+    pub phi_max: f64,
+    pub material: Material,
+}
+
+impl Torus {
+    pub fn new(major_radius: f64, minor_radius: f64, material: Material) -> Torus {
+        Torus {
+            major_radius,
+            minor_radius,
+            phi_max: 2.0 * PI,
+            material,
+        }
+    }
+
+    /// Cuts the torus down to a sector of `degrees`.
+    pub fn phi_max(mut self, degrees: f64) -> Torus {
+        self.phi_max = sweep(degrees);
+        self
+    }
+}
+
+impl Hittable for Torus {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        let length: f64 = r.direction().length();
+        if length == 0.0 {
+            return false;
+        }
+        let d: Vec3 = r.direction() / length;
+        // The quartic loses precision quickly with distance, so solve it from
+        // the point on the ray closest to the center and shift back after.
+        let shift: f64 = -Vec3::dot(&r.origin(), &d);
+        let o: Vec3 = r.origin() + d * shift;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) with p = o + s d, |d| = 1.
+        let four_r2: f64 = 4.0 * self.major_radius * self.major_radius;
+        let f: f64 = Vec3::dot(&o, &d);
+        let e: f64 = o.length_squared() + self.major_radius * self.major_radius
+            - self.minor_radius * self.minor_radius;
+        let roots: Vec<f64> = solve_quartic(
+            1.0,
+            4.0 * f,
+            4.0 * f * f + 2.0 * e - four_r2 * (d.x() * d.x() + d.z() * d.z()),
+            4.0 * e * f - 2.0 * four_r2 * (o.x() * d.x() + o.z() * d.z()),
+            e * e - four_r2 * (o.x() * o.x() + o.z() * o.z()),
+        );
+
+        for s in roots.iter() {
+            let t: f64 = (shift + s) / length;
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+            let p: Vec3 = r.point_at_parameter(t);
+            let phi: f64 = phi(&p);
+            if phi > self.phi_max {
+                continue;
+            }
+            let ring: f64 = (p.x() * p.x() + p.z() * p.z()).sqrt();
+            let center: Vec3 = if ring > 0.0 {
+                Vec3::new(p.x(), 0.0, p.z()) * (self.major_radius / ring)
+            } else {
+                Vec3::new(self.major_radius, 0.0, 0.0)
+            };
+            let mut theta: f64 = p.y().atan2(ring - self.major_radius);
+            if theta < 0.0 {
+                theta += 2.0 * PI;
+            }
+            rec.t = t;
+            rec.p = p;
+            rec.normal = Vec3::unit_vector(&(p - center));
+            rec.geometric_normal = rec.normal;
+            rec.u = phi / self.phi_max;
+            rec.v = theta / (2.0 * PI);
+            rec.barycentric = Vec3::new(0.0, 0.0, 0.0);
+            rec.material = Cow::Borrowed(&self.material);
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let outer: f64 = self.major_radius + self.minor_radius;
+        *output_box = Aabb::new(
+            Vec3::new(-outer, -self.minor_radius, -outer),
+            Vec3::new(outer, self.minor_radius, outer),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn torus() -> Torus {
+        Torus::new(
+            2.0,
+            0.5,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let torus = torus();
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+        assert!(torus.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 1.25).abs() < 1e-12);
+        assert!((rec.p.x() + 2.5).abs() < 1e-12);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-12);
+        assert!((rec.u - 0.5).abs() < 1e-12 && rec.v.abs() < 1e-12);
+        // Past the near side of the tube the next hit is its inner wall.
+        assert!(torus.hit(&r, 1.5, f64::MAX, &mut rec));
+        assert!((rec.p.x() + 1.5).abs() < 1e-12);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-12);
+
+        let down = Ray::new(Vec3::new(0.0, 3.0, 2.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus.hit(&down, 0.0, f64::MAX, &mut rec));
+        assert!((rec.t - 2.5).abs() < 1e-12);
+        assert!((rec.normal - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-12);
+        assert!((rec.v - 0.25).abs() < 1e-12);
+
+        // Through the hole, and just past the outside.
+        let hole = Ray::new(Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!torus.hit(&hole, 0.0, f64::MAX, &mut rec));
+        let outside = Ray::new(Vec3::new(-5.0, 0.51, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!torus.hit(&outside, 0.0, f64::MAX, &mut rec));
+    }
+
+    #[test]
+    fn test_far_hit() {
+        // A grazing ray from far away still lands on the surface.
+        let torus = torus();
+        let mut rec = HitRecord::new();
+        let origin = Vec3::new(-1e4, 0.3, 1e4);
+        let target = Vec3::new(0.0, 0.3, 2.0);
+        let r = Ray::new(origin, target - origin);
+        assert!(torus.hit(&r, 0.0, f64::MAX, &mut rec));
+        let ring: f64 = (rec.p.x() * rec.p.x() + rec.p.z() * rec.p.z()).sqrt();
+        let distance: f64 = ((ring - 2.0).powi(2) + rec.p.y().powi(2)).sqrt();
+        assert!((distance - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_phi_max() {
+        let half = torus().phi_max(180.0);
+        let mut rec = HitRecord::new();
+        let down = |z: f64| Ray::new(Vec3::new(0.0, 3.0, z), Vec3::new(0.0, -1.0, 0.0));
+        assert!(half.hit(&down(-2.0), 0.0, f64::MAX, &mut rec));
+        assert!((rec.u - 0.5).abs() < 1e-12);
+        assert!(!half.hit(&down(2.0), 0.0, f64::MAX, &mut rec));
+
+        let mut bbox = Aabb::empty();
+        assert!(half.bounding_box(&mut bbox));
+        assert_eq!(bbox.max, Vec3::new(2.5, 0.5, 2.5));
+    }
+}
@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+
+use rand::Rng;
+
+use super::*;
+use crate::materials::Material;
+use crate::structs::aabb::Aabb;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Padding that keeps the box of an axis-aligned triangle from being flat.
+const BOX_PADDING: f64 = 0.0001;
+
+/// Single triangle with its own vertices. Counter-clockwise vertices face the viewer.
+#[derive(Clone)]
+pub struct Triangle {
+    pub v0: Vec3,
+    pub v1: Vec3,
+    pub v2: Vec3,
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, material: Material) -> Triangle {
+        Triangle {
+            v0,
+            v1,
+            v2,
+            material,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        Vec3::unit_vector(&Vec3::cross(&(self.v1 - self.v0), &(self.v2 - self.v0)))
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection.
+///
+/// Returns `(t, b1, b2)` where `b1` and `b2` are the barycentric weights of `v1` and `v2`.
+pub fn intersect(
+    r: &Ray,
+    v0: &Vec3,
+    v1: &Vec3,
+    v2: &Vec3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let e1: Vec3 = *v1 - *v0;
+    let e2: Vec3 = *v2 - *v0;
+    let pvec: Vec3 = Vec3::cross(&r.direction(), &e2);
+    let det: f64 = Vec3::dot(&e1, &pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det: f64 = 1.0 / det;
+    let tvec: Vec3 = r.origin() - *v0;
+    let b1: f64 = Vec3::dot(&tvec, &pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec: Vec3 = Vec3::cross(&tvec, &e1);
+    let b2: f64 = Vec3::dot(&r.direction(), &qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t: f64 = Vec3::dot(&e2, &qvec) * inv_det;
+    if t < t_max && t > t_min {
+        Some((t, b1, b2))
+    } else {
+        None
+    }
+}
+
+pub fn bounding_box(v0: &Vec3, v1: &Vec3, v2: &Vec3) -> Aabb {
+    let pad = Vec3::new(BOX_PADDING, BOX_PADDING, BOX_PADDING);
+    let bbox = Aabb::surrounding_box(&Aabb::new(*v0, *v0), &Aabb::new(*v1, *v1));
+    let bbox = Aabb::surrounding_box(&bbox, &Aabb::new(*v2, *v2));
+    Aabb::new(bbox.min - pad, bbox.max + pad)
+}
+
+/// Density over solid angle of `random_point` as seen from `origin`, if the ray
+/// along `direction` hits the triangle.
+pub fn pdf_value(v0: &Vec3, v1: &Vec3, v2: &Vec3, origin: &Vec3, direction: &Vec3) -> f64 {
+    let r = Ray::new(*origin, *direction);
+    let t: f64 = match intersect(&r, v0, v1, v2, 0.001, f64::MAX) {
+        Some((t, _, _)) => t,
+        None => return 0.0,
+    };
+    let normal: Vec3 = Vec3::cross(&(*v1 - *v0), &(*v2 - *v0));
+    let area: f64 = 0.5 * normal.length();
+    let distance_squared: f64 = t * t * direction.length_squared();
+    let cosine: f64 =
+        (Vec3::dot(direction, &normal) / (direction.length() * normal.length())).abs();
+    // Seen edge on, or degenerate, the triangle covers no solid angle.
+    if cosine * area < 1e-12 {
+        return 0.0;
+    }
+    distance_squared / (cosine * area)
+}
+
+/// Uniformly distributed point on the triangle.
+pub fn random_point(v0: &Vec3, v1: &Vec3, v2: &Vec3, sampler: &mut Sampler) -> Vec3 {
+    let s: f64 = sampler.gen::<f64>().sqrt();
+    let t: f64 = sampler.gen::<f64>();
+    *v0 * (1.0 - s) + *v1 * (s * (1.0 - t)) + *v2 * (s * t)
+}
+
+impl Hittable for Triangle {
+    fn hit<'a>(&'a self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord<'a>) -> bool {
+        match intersect(r, &self.v0, &self.v1, &self.v2, t_min, t_max) {
+            Some((t, b1, b2)) => {
+                rec.t = t;
+                rec.p = r.point_at_parameter(t);
+                rec.geometric_normal = self.normal();
+                rec.normal = rec.geometric_normal;
+                rec.u = b1;
+                rec.v = b2;
+                rec.barycentric = Vec3::new(1.0 - b1 - b2, b1, b2);
+                rec.material = Cow::Borrowed(&self.material);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = bounding_box(&self.v0, &self.v1, &self.v2);
+        true
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        pdf_value(&self.v0, &self.v1, &self.v2, origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3, sampler: &mut Sampler) -> Option<Vec3> {
+        Some(random_point(&self.v0, &self.v1, &self.v2, sampler) - *origin)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, -1.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn test_hit() {
+        let r = Ray::new(Vec3::new(0.25, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let triangle = triangle();
+        let mut rec = HitRecord::new();
+        assert!(triangle.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.p, Vec3::new(0.25, 0.5, -1.0));
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(rec.barycentric, Vec3::new(0.25, 0.25, 0.5));
+    }
+
+    #[test]
+    fn test_miss() {
+        let outside = Ray::new(Vec3::new(0.75, 0.75, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let parallel = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        let triangle = triangle();
+        let mut rec = HitRecord::new();
+        assert!(!triangle.hit(&outside, 0.0, f64::MAX, &mut rec));
+        assert!(!triangle.hit(&parallel, 0.0, f64::MAX, &mut rec));
+    }
+
+    #[test]
+    fn test_pdf_value() {
+        let triangle = triangle();
+        let origin = Vec3::new(0.25, 0.25, 1.0);
+        let mut sampler = Sampler::new(0);
+        let direction: Vec3 = triangle.random(&origin, &mut sampler).unwrap();
+        // Area 1/2 straight below at distance 2.
+        let straight: f64 = triangle.pdf_value(&origin, &Vec3::new(0.0, 0.0, -1.0));
+        assert!((straight - 8.0).abs() < 1e-12);
+        assert!(triangle.pdf_value(&origin, &direction) > 0.0);
+
+        // At a grazing angle the triangle gets no density instead of a huge one.
+        let floor = Triangle::new(
+            Vec3::new(-1.0, 0.0, -1.0),
+            Vec3::new(1.0, 0.0, -1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        );
+        let grazing = Vec3::new(0.0, -1e-10, -500.0);
+        let origin = Vec3::new(0.0, 1e-10, 499.5);
+        let mut rec = HitRecord::new();
+        assert!(floor.hit(&Ray::new(origin, grazing), 0.001, f64::MAX, &mut rec));
+        assert_eq!(floor.pdf_value(&origin, &grazing), 0.0);
+    }
+
+    #[test]
+    fn test_back_face() {
+        let r = Ray::new(Vec3::new(0.25, 0.25, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let triangle = triangle();
+        let mut rec = HitRecord::new();
+        assert!(triangle.hit(&r, 0.0, f64::MAX, &mut rec));
+        assert_eq!(rec.t, 1.0);
+    }
+}
@@ -0,0 +1,387 @@
+use std::io::{self, Write};
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+use crate::render::aov::Aov;
+use crate::render::framebuffer::Framebuffer;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+
+/// Storage type of every channel in an OpenEXR file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelType {
+    Half,
+    Float,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    /// zlib over blocks of 16 scanlines.
+    Zip,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExrOptions {
+    pub pixel_type: PixelType,
+    pub compression: Compression,
+}
+
+impl Default for ExrOptions {
+    fn default() -> ExrOptions {
+        ExrOptions {
+            pixel_type: PixelType::Half,
+            compression: Compression::Zip,
+        }
+    }
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> usize {
+        match self {
+            Compression::None => 1,
+            Compression::Zip => 16,
+        }
+    }
+}
+
+/// One channel of the file: its name and where its values come from.
+struct Channel<'a> {
+    name: String,
+    source: Source<'a>,
+}
+
+enum Source<'a> {
+    Color(usize),
+    Aov(&'a Aov, usize),
+}
+
+/// Writes a single-part scanline OpenEXR file with the linear color as `R`,
+/// `G` and `B` and every AOV as extra channels.
+pub fn write_exr<W: Write>(
+    w: &mut W,
+    image: &Framebuffer,
+    aovs: &[Aov],
+    options: ExrOptions,
+) -> io::Result<()> {
+    let mut channels: Vec<Channel> = ["R", "G", "B"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| Channel {
+            name: name.to_string(),
+            source: Source::Color(i),
+        })
+        .collect();
+    for aov in aovs.iter() {
+        for (i, name) in aov.channel_names().into_iter().enumerate() {
+            channels.push(Channel {
+                name,
+                source: Source::Aov(aov, i),
+            });
+        }
+    }
+    // Readers expect the channel list in alphabetical order.
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let header: Vec<u8> = header(image, &channels, options);
+    let blocks: Vec<Vec<u8>> = (0..image.height)
+        .step_by(options.compression.lines_per_block())
+        .map(|y0| block(image, &channels, options, y0))
+        .collect();
+
+    w.write_all(&header)?;
+    let mut offset: u64 = (header.len() + 8 * blocks.len()) as u64;
+    for block in blocks.iter() {
+        w.write_all(&offset.to_le_bytes())?;
+        offset += block.len() as u64;
+    }
+    for block in blocks.iter() {
+        w.write_all(block)?;
+    }
+    Ok(())
+}
+
+fn header(image: &Framebuffer, channels: &[Channel], options: ExrOptions) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&2u32.to_le_bytes());
+
+    let mut chlist: Vec<u8> = Vec::new();
+    for channel in channels.iter() {
+        chlist.extend_from_slice(channel.name.as_bytes());
+        chlist.push(0);
+        let pixel_type: i32 = match options.pixel_type {
+            PixelType::Half => 1,
+            PixelType::Float => 2,
+        };
+        chlist.extend_from_slice(&pixel_type.to_le_bytes());
+        // pLinear and three reserved bytes, then x and y sampling.
+        chlist.extend_from_slice(&[0, 0, 0, 0]);
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+        chlist.extend_from_slice(&1i32.to_le_bytes());
+    }
+    chlist.push(0);
+    attribute(&mut out, "channels", "chlist", &chlist);
+
+    attribute(
+        &mut out,
+        "compression",
+        "compression",
+        &[options.compression.id()],
+    );
+    let mut window: Vec<u8> = Vec::new();
+    for v in [0, 0, image.width as i32 - 1, image.height as i32 - 1].iter() {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut out, "dataWindow", "box2i", &window);
+    attribute(&mut out, "displayWindow", "box2i", &window);
+    attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+    attribute(&mut out, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    attribute(&mut out, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(&mut out, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    out.push(0);
+    out
+}
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// Scanlines starting at `y0`, prefixed with their y coordinate and size.
+fn block(image: &Framebuffer, channels: &[Channel], options: ExrOptions, y0: usize) -> Vec<u8> {
+    let y1: usize = (y0 + options.compression.lines_per_block()).min(image.height);
+    let mut data: Vec<u8> = Vec::new();
+    for y in y0..y1 {
+        for channel in channels.iter() {
+            for x in 0..image.width {
+                let pixel: usize = y * image.width + x;
+                let value: f64 = match channel.source {
+                    Source::Color(i) => image.pixels[pixel][i],
+                    Source::Aov(aov, i) => aov.value(i, pixel),
+                };
+                match options.pixel_type {
+                    PixelType::Half => {
+                        data.extend_from_slice(&f32_to_half(value as f32).to_le_bytes())
+                    }
+                    PixelType::Float => data.extend_from_slice(&(value as f32).to_le_bytes()),
+                }
+            }
+        }
+    }
+    if options.compression == Compression::Zip {
+        let compressed: Vec<u8> = zip_compress(&data);
+        // Blocks that do not shrink are stored as they are.
+        if compressed.len() < data.len() {
+            data = compressed;
+        }
+    }
+
+    let mut out: Vec<u8> = Vec::with_capacity(data.len() + 8);
+    out.extend_from_slice(&(y0 as i32).to_le_bytes());
+    out.extend_from_slice(&(data.len() as i32).to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+/// Splits even and odd bytes into two halves and delta-encodes the result
+/// before deflating, as OpenEXR's ZIP compression expects.
+fn zip_compress(data: &[u8]) -> Vec<u8> {
+    let mut reordered: Vec<u8> = Vec::with_capacity(data.len());
+    reordered.extend(data.iter().step_by(2));
+    reordered.extend(data.iter().skip(1).step_by(2));
+    let mut previous: u8 = reordered.first().copied().unwrap_or(0);
+    for byte in reordered.iter_mut().skip(1) {
+        let current: u8 = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+    compress_to_vec_zlib(&reordered, 6)
+}
+
+/// Rounds to the nearest IEEE 754 half, overflowing to infinity.
+pub fn f32_to_half(value: f32) -> u16 {
+    let bits: u32 = value.to_bits();
+    let sign: u16 = ((bits >> 16) & 0x8000) as u16;
+    let exponent: i32 = ((bits >> 23) & 0xff) as i32;
+    let mantissa: u32 = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        let nan: u16 = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let half_exponent: i32 = exponent - 127 + 15;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if half_exponent <= 0 {
+        // Subnormal half, or zero if even rounding cannot reach the smallest one.
+        if half_exponent < -10 {
+            return sign;
+        }
+        let m: u32 = mantissa | 0x80_0000;
+        let shift: u32 = (14 - half_exponent) as u32;
+        return sign | round_shift(m, shift) as u16;
+    }
+    // A carry out of the mantissa correctly bumps the exponent.
+    sign | (((half_exponent as u32) << 10) + round_shift(mantissa, 13)) as u16
+}
+
+/// `m >> shift`, rounded to nearest with ties to even.
+fn round_shift(m: u32, shift: u32) -> u32 {
+    let truncated: u32 = m >> shift;
+    let remainder: u32 = m & ((1 << shift) - 1);
+    let halfway: u32 = 1 << (shift - 1);
+    if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structs::vec3::Vec3;
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    /// Length of the header, found by walking its attributes.
+    fn header_len(bytes: &[u8]) -> usize {
+        let mut at: usize = 8;
+        loop {
+            let name_end: usize = at + bytes[at..].iter().position(|b| *b == 0).unwrap();
+            if name_end == at {
+                return at + 1;
+            }
+            let kind_end: usize =
+                name_end + 1 + bytes[name_end + 1..].iter().position(|b| *b == 0).unwrap();
+            let size: [u8; 4] = [
+                bytes[kind_end + 1],
+                bytes[kind_end + 2],
+                bytes[kind_end + 3],
+                bytes[kind_end + 4],
+            ];
+            at = kind_end + 5 + i32::from_le_bytes(size) as usize;
+        }
+    }
+
+    #[test]
+    fn test_f32_to_half() {
+        assert_eq!(f32_to_half(0.0), 0x0000);
+        assert_eq!(f32_to_half(-0.0), 0x8000);
+        assert_eq!(f32_to_half(1.0), 0x3c00);
+        assert_eq!(f32_to_half(0.5), 0x3800);
+        assert_eq!(f32_to_half(-2.0), 0xc000);
+        assert_eq!(f32_to_half(65504.0), 0x7bff);
+        assert_eq!(f32_to_half(1.0e6), 0x7c00);
+        assert_eq!(f32_to_half(f32::INFINITY), 0x7c00);
+        assert_eq!(f32_to_half(f32::NAN) & 0x7e00, 0x7e00);
+        // Smallest subnormal, and ties rounding to even.
+        assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(2f32.powi(-25)), 0x0000);
+        assert_eq!(f32_to_half(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_half(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+        assert_eq!(f32_to_half(0.1), 0x2e66);
+    }
+
+    fn image() -> Framebuffer {
+        let mut image = Framebuffer::new(3, 2);
+        image.set(0, 0, Vec3::new(1.0, 2.0, 3.0));
+        image.set(2, 1, Vec3::new(0.5, 100.0, -1.0));
+        image
+    }
+
+    #[test]
+    fn test_write_uncompressed_float() {
+        let options = ExrOptions {
+            pixel_type: PixelType::Float,
+            compression: Compression::None,
+        };
+        let depth = Aov::Scalar {
+            name: "Z".to_string(),
+            values: vec![4.0; 6],
+        };
+        let mut bytes: Vec<u8> = Vec::new();
+        write_exr(&mut bytes, &image(), &[depth], options).unwrap();
+
+        assert_eq!(&bytes[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let header_end: usize = header_len(&bytes);
+        assert!(bytes[..header_end].windows(9).any(|w| w == b"channels\0"));
+        assert_eq!(header_end + 16 + 2 * (8 + 48), bytes.len());
+
+        // Two single-line blocks of 4 channels (B, G, R, Z) by 3 pixels.
+        let block_size: usize = 8 + 4 * 3 * 4;
+        let tail: &[u8] = &bytes[bytes.len() - 2 * (block_size + 8)..];
+        let first: u64 = u64::from_le_bytes([
+            tail[0], tail[1], tail[2], tail[3], tail[4], tail[5], tail[6], tail[7],
+        ]);
+        assert_eq!(first as usize, bytes.len() - 2 * block_size);
+
+        let second: &[u8] = &bytes[bytes.len() - block_size..];
+        assert_eq!(&second[..8], &[1, 0, 0, 0, 48, 0, 0, 0]);
+        let value = |channel: usize, x: usize| -> f32 {
+            let at: usize = 8 + 4 * (3 * channel + x);
+            f32::from_le_bytes([second[at], second[at + 1], second[at + 2], second[at + 3]])
+        };
+        assert_eq!(value(0, 2), -1.0);
+        assert_eq!(value(1, 2), 100.0);
+        assert_eq!(value(2, 2), 0.5);
+        assert_eq!(value(3, 0), 4.0);
+    }
+
+    #[test]
+    fn test_write_zip_half() {
+        let mut image = Framebuffer::new(40, 20);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            *pixel = Vec3::new(i as f64, 1.0, 0.0);
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        write_exr(&mut bytes, &image, &[], ExrOptions::default()).unwrap();
+
+        // 20 lines make a full block of 16 and a remainder of 4.
+        let header_end: usize = header_len(&bytes);
+        let offset = |i: usize| -> usize {
+            let at: usize = header_end + 8 * i;
+            let mut le = [0u8; 8];
+            le.copy_from_slice(&bytes[at..at + 8]);
+            u64::from_le_bytes(le) as usize
+        };
+        let (first, second) = (offset(0), offset(1));
+        assert_eq!(first, header_end + 16);
+        assert_eq!(&bytes[second..second + 4], &[16, 0, 0, 0]);
+
+        let size: usize = i32::from_le_bytes([
+            bytes[first + 4],
+            bytes[first + 5],
+            bytes[first + 6],
+            bytes[first + 7],
+        ]) as usize;
+        assert_eq!(first + 8 + size, second);
+        let raw_size: usize = 16 * 3 * 40 * 2;
+        assert!(size < raw_size);
+
+        // Undo the predictor and the byte interleaving.
+        let mut data = decompress_to_vec_zlib(&bytes[first + 8..second]).unwrap();
+        assert_eq!(data.len(), raw_size);
+        for i in 1..data.len() {
+            data[i] = data[i].wrapping_add(data[i - 1]).wrapping_sub(128);
+        }
+        let (even, odd) = data.split_at(raw_size / 2);
+        let half = |i: usize| -> u16 { u16::from_le_bytes([even[i], odd[i]]) };
+        // First line: B = 0, then G = 1, then R = x.
+        assert_eq!(half(0), 0x0000);
+        assert_eq!(half(40), 0x3c00);
+        assert_eq!(half(80 + 2), f32_to_half(2.0));
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use crate::render::framebuffer::Framebuffer;
+use crate::structs::vec3::Vec3;
+
+/// Writes a Radiance RGBE (`.hdr`) image without run-length encoding.
+pub fn write_hdr<W: Write>(w: &mut W, image: &Framebuffer) -> io::Result<()> {
+    write!(
+        w,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        image.height, image.width
+    )?;
+    let mut data: Vec<u8> = Vec::with_capacity(4 * image.pixels.len());
+    for color in image.pixels.iter() {
+        data.extend_from_slice(&to_rgbe(*color));
+    }
+    w.write_all(&data)
+}
+
+/// Shared-exponent encoding of a color; negative components become 0.
+pub fn to_rgbe(color: Vec3) -> [u8; 4] {
+    let r: f64 = color.x().max(0.0);
+    let g: f64 = color.y().max(0.0);
+    let b: f64 = color.z().max(0.0);
+    let v: f64 = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    if v.is_infinite() {
+        return [255, 255, 255, 255];
+    }
+    // v = m * 2^e with m in [0.5, 1).
+    let mut e: i32 = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1.0 {
+        e += 1;
+    }
+    if e > 127 {
+        return [255, 255, 255, 255];
+    }
+    let scale: f64 = 256.0 / 2f64.powi(e);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_rgbe() {
+        assert_eq!(to_rgbe(Vec3::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(Vec3::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe(Vec3::new(0.5, 0.25, -1.0)), [128, 64, 0, 128]);
+        assert_eq!(to_rgbe(Vec3::new(1000.0, 0.0, 0.0)), [250, 0, 0, 138]);
+        assert_eq!(to_rgbe(Vec3::new(f64::NAN, 0.0, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_write_hdr() {
+        let mut image = Framebuffer::new(2, 1);
+        image.set(1, 0, Vec3::new(4.0, 2.0, 1.0));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_hdr(&mut bytes, &image).unwrap();
+        let header: &[u8] = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[0, 0, 0, 0, 128, 64, 32, 131]);
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use self::exr::ExrOptions;
+use crate::render::aov::Aov;
+use crate::render::framebuffer::Framebuffer;
+use crate::render::tonemap::{srgb_encode, ToneMapper};
+use crate::structs::vec3::Vec3;
+
+pub mod exr;
+pub mod hdr;
+pub mod png;
+pub mod ppm;
+
+/// File format of a saved image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    /// ASCII `P3` PPM.
+    PpmAscii,
+    /// Binary `P6` PPM.
+    PpmBinary,
+    /// Portable float map with 32-bit linear values.
+    Pfm,
+    Exr(ExrOptions),
+    Hdr,
+}
+
+impl ImageFormat {
+    /// Format for the extension of `path`: `.png`, `.ppm` (binary), `.pfm`,
+    /// `.exr` or `.hdr`.
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        let extension: String = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "ppm" => Some(ImageFormat::PpmBinary),
+            "pfm" => Some(ImageFormat::Pfm),
+            "exr" => Some(ImageFormat::Exr(ExrOptions::default())),
+            "hdr" => Some(ImageFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    /// Whether the format keeps linear radiance. The others store display
+    /// values, so renders should be tone mapped before they are saved.
+    pub fn is_linear(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Pfm | ImageFormat::Exr(_) | ImageFormat::Hdr
+        )
+    }
+}
+
+/// Writes `image` to `path`, picking the format from the file extension.
+pub fn save<P: AsRef<Path>>(path: P, image: &Framebuffer) -> io::Result<()> {
+    save_with_aovs(path, image, &[])
+}
+
+/// Like `save`, but first tone maps `image` with `tone_mapper` unless the
+/// format keeps linear radiance.
+pub fn save_tone_mapped<P: AsRef<Path>>(
+    path: P,
+    image: &Framebuffer,
+    tone_mapper: &ToneMapper,
+) -> io::Result<()> {
+    let path: &Path = path.as_ref();
+    if matches!(ImageFormat::from_path(path), Some(format) if format.is_linear()) {
+        save(path, image)
+    } else {
+        save(path, &tone_mapper.apply(image))
+    }
+}
+
+/// Like `save`, but also writes `aovs`, see `save_as`.
+pub fn save_with_aovs<P: AsRef<Path>>(
+    path: P,
+    image: &Framebuffer,
+    aovs: &[Aov],
+) -> io::Result<()> {
+    let path: &Path = path.as_ref();
+    let format: ImageFormat = ImageFormat::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported image format: {}", path.display()),
+        )
+    })?;
+    save_as(path, image, aovs, format)
+}
+
+/// Writes `image` and `aovs` to `path` in `format`. AOVs become extra
+/// channels of an OpenEXR file, or one `<stem>.<aov name>.hdr` or `.pfm`
+/// image each next to a Radiance or float map file. The 8-bit formats have
+/// no room for them and leave them out.
+///
+/// Every file is written to a temporary file first and renamed over `path`,
+/// so an interrupted write never leaves a truncated or mixed image behind.
+pub fn save_as<P: AsRef<Path>>(
+    path: P,
+    image: &Framebuffer,
+    aovs: &[Aov],
+    format: ImageFormat,
+) -> io::Result<()> {
+    let path: &Path = path.as_ref();
+    write_atomic(path, |w| match format {
+        ImageFormat::Png => png::write_png(w, image),
+        ImageFormat::PpmAscii => ppm::write_p3(w, image),
+        ImageFormat::PpmBinary => ppm::write_p6(w, image),
+        ImageFormat::Pfm => ppm::write_pfm(w, image),
+        ImageFormat::Exr(options) => exr::write_exr(w, image, aovs, options),
+        ImageFormat::Hdr => hdr::write_hdr(w, image),
+    })?;
+
+    let extension: &str = match format {
+        ImageFormat::Hdr => "hdr",
+        ImageFormat::Pfm => "pfm",
+        _ => return Ok(()),
+    };
+    let stem: &str = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    for aov in aovs.iter() {
+        let aov_path = path.with_file_name(format!("{}.{}.{}", stem, aov.name(), extension));
+        let aov_image: Framebuffer = aov_image(aov, image.width, image.height);
+        write_atomic(&aov_path, |w| match format {
+            ImageFormat::Pfm => ppm::write_pfm(w, &aov_image),
+            _ => hdr::write_hdr(w, &aov_image),
+        })?;
+    }
+    Ok(())
+}
+
+/// Numbers the temporary files of one process, so that threads saving the
+/// same path at once do not write to the same temporary file.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Calls `write` on a temporary file next to `path`, then renames it to `path`.
+/// The temporary file is removed again if anything fails.
+fn write_atomic<F>(path: &Path, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let name: String = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path: PathBuf = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name,
+        process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = File::create(&temp_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        let file: File = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Encodes a display-linear color with the sRGB transfer function, clamping
+/// to [0, 1]. Renders should go through a `ToneMapper` first.
+pub fn to_rgb8(color: Vec3) -> [u8; 3] {
+    let encode = |c: f64| -> u8 { (255.0 * srgb_encode(c.clamp(0.0, 1.0)) + 0.5) as u8 };
+    [encode(color.x()), encode(color.y()), encode(color.z())]
+}
+
+/// Puts an AOV into an RGB image, repeating scalars in all three channels.
+fn aov_image(aov: &Aov, width: usize, height: usize) -> Framebuffer {
+    let mut image = Framebuffer::new(width, height);
+    let channels: usize = aov.channel_names().len();
+    for (i, pixel) in image.pixels.iter_mut().enumerate() {
+        let value = |c: usize| -> f64 { aov.value(c.min(channels - 1), i) };
+        *pixel = Vec3::new(value(0), value(1), value(2));
+    }
+    image
+}
+
+/// 8-bit RGB bytes of every pixel, row by row from the top-left pixel.
+fn rgb8_pixels(image: &Framebuffer) -> Vec<u8> {
+    image
+        .pixels
+        .iter()
+        .flat_map(|c| to_rgb8(*c).to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_rgb8() {
+        assert_eq!(to_rgb8(Vec3::new(0.0, 0.25, 1.0)), [0, 137, 255]);
+        assert_eq!(to_rgb8(Vec3::new(-1.0, 4.0, f64::NAN)), [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_overwrite_truncates() {
+        let dir = std::env::temp_dir().join(format!("ray_tracing_atomic_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("img.ppm");
+
+        save(&path, &Framebuffer::new(64, 64)).unwrap();
+        save(&path, &Framebuffer::new(2, 1)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap().len(), 11 + 6);
+
+        // A failed write leaves the previous image and no temporary file.
+        let failed = write_atomic(&path, |_| Err(io::Error::from(io::ErrorKind::Other)));
+        assert!(failed.is_err());
+        assert_eq!(std::fs::read(&path).unwrap().len(), 11 + 6);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // Threads saving the same path at once each write a whole image.
+        let threads: Vec<_> = (1..=8)
+            .map(|width| {
+                let path = path.clone();
+                std::thread::spawn(move || save(&path, &Framebuffer::new(width, 1)))
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+        let size: usize = std::fs::read(&path).unwrap().len();
+        assert!((1..=8).any(|width| size == 11 + 3 * width), "{}", size);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_by_extension() {
+        let dir = std::env::temp_dir().join(format!("ray_tracing_output_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = Framebuffer::new(2, 2);
+
+        save(dir.join("a.png"), &image).unwrap();
+        let png_bytes = std::fs::read(dir.join("a.png")).unwrap();
+        assert_eq!(&png_bytes[..8], b"\x89PNG\r\n\x1a\n");
+
+        save(dir.join("a.PPM"), &image).unwrap();
+        let ppm_bytes = std::fs::read(dir.join("a.PPM")).unwrap();
+        assert!(ppm_bytes.starts_with(b"P6\n2 2\n255\n"));
+
+        save_as(dir.join("a.PPM"), &image, &[], ImageFormat::PpmAscii).unwrap();
+        let ppm_bytes = std::fs::read(dir.join("a.PPM")).unwrap();
+        assert!(ppm_bytes.starts_with(b"P3\n2 2\n255\n"));
+
+        let aovs = vec![Aov::Scalar {
+            name: "depth".to_string(),
+            values: vec![2.0; 4],
+        }];
+        save_with_aovs(dir.join("a.exr"), &image, &aovs).unwrap();
+        let exr_bytes = std::fs::read(dir.join("a.exr")).unwrap();
+        assert_eq!(&exr_bytes[..4], &[0x76, 0x2f, 0x31, 0x01]);
+
+        save_with_aovs(dir.join("a.hdr"), &image, &aovs).unwrap();
+        let hdr_bytes = std::fs::read(dir.join("a.hdr")).unwrap();
+        assert!(hdr_bytes.starts_with(b"#?RADIANCE\n"));
+        let depth_bytes = std::fs::read(dir.join("a.depth.hdr")).unwrap();
+        assert_eq!(&depth_bytes[depth_bytes.len() - 4..], &[128, 128, 128, 130]);
+
+        assert!(ImageFormat::from_path(&dir.join("a.exr"))
+            .unwrap()
+            .is_linear());
+        assert!(!ImageFormat::from_path(&dir.join("a.png"))
+            .unwrap()
+            .is_linear());
+        let err = save(dir.join("a.bmp"), &image).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = save(dir.join("missing").join("a.png"), &image).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+use super::rgb8_pixels;
+use crate::render::framebuffer::Framebuffer;
+
+pub(crate) const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Writes an 8-bit RGB PNG.
+pub fn write_png<W: Write>(w: &mut W, image: &Framebuffer) -> io::Result<()> {
+    w.write_all(SIGNATURE)?;
+
+    let mut header: Vec<u8> = Vec::with_capacity(13);
+    header.extend_from_slice(&(image.width as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height as u32).to_be_bytes());
+    // Bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(w, b"IHDR", &header)?;
+
+    // Every scanline starts with its filter type; 0 leaves the bytes as they are.
+    let pixels: Vec<u8> = rgb8_pixels(image);
+    let mut scanlines: Vec<u8> = Vec::with_capacity(pixels.len() + image.height);
+    for row in pixels.chunks(3 * image.width.max(1)) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    write_chunk(w, b"IDAT", &compress_to_vec_zlib(&scanlines, 6))?;
+    write_chunk(w, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    let crc: u32 = crc32(crc32(0, kind), data);
+    w.write_all(&crc.to_be_bytes())
+}
+
+/// CRC-32 as used by PNG and zlib, continuing from `crc`.
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut c: u32 = !crc;
+    for byte in data {
+        c ^= *byte as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+    }
+    !c
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structs::vec3::Vec3;
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+        // CRC of an empty IEND chunk, as found at the end of every PNG.
+        assert_eq!(crc32(0, b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut image = Framebuffer::new(2, 2);
+        image.set(1, 0, Vec3::new(1.0, 0.0, 0.0));
+        image.set(0, 1, Vec3::new(0.0, 0.25, 1.0));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_png(&mut bytes, &image).unwrap();
+
+        assert_eq!(&bytes[..8], SIGNATURE);
+        assert_eq!(&bytes[12..16], b"IHDR");
+        assert_eq!(&bytes[16..24], &[0, 0, 0, 2, 0, 0, 0, 2]);
+        assert_eq!(&bytes[bytes.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        let idat_len = u32::from_be_bytes([bytes[33], bytes[34], bytes[35], bytes[36]]) as usize;
+        assert_eq!(&bytes[37..41], b"IDAT");
+        let idat: &[u8] = &bytes[41..41 + idat_len];
+        let crc = u32::from_be_bytes([
+            bytes[41 + idat_len],
+            bytes[42 + idat_len],
+            bytes[43 + idat_len],
+            bytes[44 + idat_len],
+        ]);
+        assert_eq!(crc, crc32(crc32(0, b"IDAT"), idat));
+        assert_eq!(
+            decompress_to_vec_zlib(idat).unwrap(),
+            vec![0, 0, 0, 0, 255, 0, 0, 0, 0, 137, 255, 0, 0, 0]
+        );
+    }
+}
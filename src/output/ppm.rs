@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+
+use super::{rgb8_pixels, to_rgb8};
+use crate::render::framebuffer::Framebuffer;
+
+/// Writes an ASCII `P3` PPM with one pixel per line.
+pub fn write_p3<W: Write>(w: &mut W, image: &Framebuffer) -> io::Result<()> {
+    write!(w, "P3\n{} {}\n255\n", image.width, image.height)?;
+    for color in image.pixels.iter() {
+        let [r, g, b] = to_rgb8(*color);
+        writeln!(w, "{} {} {}", r, g, b)?;
+    }
+    Ok(())
+}
+
+/// Writes a binary `P6` PPM.
+pub fn write_p6<W: Write>(w: &mut W, image: &Framebuffer) -> io::Result<()> {
+    write!(w, "P6\n{} {}\n255\n", image.width, image.height)?;
+    w.write_all(&rgb8_pixels(image))
+}
+
+/// Writes a color portable float map with linear little-endian values.
+/// PFM stores rows from the bottom up.
+pub fn write_pfm<W: Write>(w: &mut W, image: &Framebuffer) -> io::Result<()> {
+    // A negative scale marks the data as little-endian.
+    write!(w, "PF\n{} {}\n-1.0\n", image.width, image.height)?;
+    let mut data: Vec<u8> = Vec::with_capacity(12 * image.pixels.len());
+    for row in image.pixels.chunks(image.width.max(1)).rev() {
+        for color in row.iter() {
+            for c in 0..3 {
+                data.extend_from_slice(&(color[c] as f32).to_le_bytes());
+            }
+        }
+    }
+    w.write_all(&data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structs::vec3::Vec3;
+
+    #[test]
+    fn test_write_p3() {
+        let mut image = Framebuffer::new(2, 1);
+        image.set(1, 0, Vec3::new(1.0, 0.25, 0.0));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_p3(&mut bytes, &image).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "P3\n2 1\n255\n0 0 0\n255 137 0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_p6() {
+        let mut image = Framebuffer::new(2, 1);
+        image.set(1, 0, Vec3::new(1.0, 0.25, 0.0));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_p6(&mut bytes, &image).unwrap();
+        assert_eq!(bytes, b"P6\n2 1\n255\n\0\0\0\xff\x89\0".to_vec());
+    }
+
+    #[test]
+    fn test_write_pfm() {
+        let mut image = Framebuffer::new(1, 2);
+        image.set(0, 0, Vec3::new(2.0, 0.5, -1.0));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_pfm(&mut bytes, &image).unwrap();
+
+        let header: &[u8] = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        // The top row comes last.
+        assert_eq!(floats, vec![0.0, 0.0, 0.0, 2.0, 0.5, -1.0]);
+    }
+}
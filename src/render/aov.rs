@@ -0,0 +1,111 @@
+use crate::objects::camera::Camera;
+use crate::objects::{HitRecord, Hittable};
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Extra per-pixel data ("arbitrary output variable") written next to the
+/// color, stored row by row from the top-left pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aov {
+    Scalar { name: String, values: Vec<f64> },
+    Vector { name: String, values: Vec<Vec3> },
+}
+
+impl Aov {
+    pub fn name(&self) -> &str {
+        match self {
+            Aov::Scalar { name, .. } | Aov::Vector { name, .. } => name,
+        }
+    }
+
+    /// Names of the channels of this AOV in an image file: the plain name for
+    /// scalars, `name.X`, `name.Y` and `name.Z` for vectors.
+    pub fn channel_names(&self) -> Vec<String> {
+        match self {
+            Aov::Scalar { name, .. } => vec![name.clone()],
+            Aov::Vector { name, .. } => ["X", "Y", "Z"]
+                .iter()
+                .map(|axis| format!("{}.{}", name, axis))
+                .collect(),
+        }
+    }
+
+    /// Value of channel `channel` (an index into `channel_names`) at `pixel`.
+    pub fn value(&self, channel: usize, pixel: usize) -> f64 {
+        match self {
+            Aov::Scalar { values, .. } => values[pixel],
+            Aov::Vector { values, .. } => values[pixel][channel],
+        }
+    }
+}
+
+/// Renders the distance to the first hit (`depth`, infinite where nothing is
+/// hit) and its shading normal (`N`) through the center of every pixel.
+pub fn render_aovs(world: &dyn Hittable, camera: &Camera, width: usize, height: usize) -> Vec<Aov> {
+    let mut depth: Vec<f64> = Vec::with_capacity(width * height);
+    let mut normals: Vec<Vec3> = Vec::with_capacity(width * height);
+    let mut rec = HitRecord::new();
+    for y in 0..height {
+        let j: usize = height - 1 - y;
+        for i in 0..width {
+            let mut sampler = Sampler::for_pixel(0, i, j, 0);
+            let u: f64 = (i as f64 + 0.5) / width as f64;
+            let v: f64 = (j as f64 + 0.5) / height as f64;
+            let r: Ray = camera.get_ray(u, v, &mut sampler);
+            if world.hit(&r, 0.001, f64::MAX, &mut rec) {
+                depth.push(rec.t * r.direction().length());
+                normals.push(rec.normal);
+            } else {
+                depth.push(f64::INFINITY);
+                normals.push(Vec3::new(0.0, 0.0, 0.0));
+            }
+        }
+    }
+    vec![
+        Aov::Scalar {
+            name: "depth".to_string(),
+            values: depth,
+        },
+        Aov::Vector {
+            name: "N".to_string(),
+            values: normals,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::HittableList;
+
+    #[test]
+    fn test_render_aovs() {
+        let mut world = HittableList::new();
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, -3.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.0,
+            1.0,
+        );
+        let aovs = render_aovs(&world, &camera, 3, 3);
+        assert_eq!(aovs[0].name(), "depth");
+        assert_eq!(aovs[1].channel_names(), vec!["N.X", "N.Y", "N.Z"]);
+
+        // The center pixel looks straight at the sphere.
+        assert!((aovs[0].value(0, 4) - 2.0).abs() < 1e-9);
+        assert!((aovs[1].value(2, 4) - 1.0).abs() < 1e-9);
+        assert_eq!(aovs[0].value(0, 0), f64::INFINITY);
+    }
+}
@@ -0,0 +1,128 @@
+use super::framebuffer::Framebuffer;
+use crate::structs::vec3::Vec3;
+
+/// Accumulates linear RGB samples per pixel, stored row by row from the
+/// top-left pixel.
+///
+/// A film keeps the sum of its samples and how many there were rather than
+/// the average, so that tiles and passes rendered separately can be added
+/// together without losing weight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Film {
+    pub width: usize,
+    pub height: usize,
+    sums: Vec<Vec3>,
+    counts: Vec<u32>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize) -> Film {
+        Film {
+            width,
+            height,
+            sums: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+            counts: vec![0; width * height],
+        }
+    }
+
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Vec3) {
+        let index: usize = y * self.width + x;
+        self.sums[index] += color;
+        self.counts[index] += 1;
+    }
+
+    pub fn sum(&self, x: usize, y: usize) -> Vec3 {
+        self.sums[y * self.width + x]
+    }
+
+    pub fn sample_count(&self, x: usize, y: usize) -> u32 {
+        self.counts[y * self.width + x]
+    }
+
+    /// Average of the samples of a pixel, black if it has none.
+    pub fn pixel(&self, x: usize, y: usize) -> Vec3 {
+        let index: usize = y * self.width + x;
+        if self.counts[index] == 0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        self.sums[index] / self.counts[index] as f64
+    }
+
+    /// Adds the samples of `tile` to the pixels starting at (`x0`, `y0`).
+    /// Parts of the tile that fall outside this film are dropped.
+    pub fn merge_tile(&mut self, tile: &Film, x0: usize, y0: usize) {
+        for y in 0..tile.height.min(self.height.saturating_sub(y0)) {
+            for x in 0..tile.width.min(self.width.saturating_sub(x0)) {
+                let from: usize = y * tile.width + x;
+                let to: usize = (y0 + y) * self.width + x0 + x;
+                self.sums[to] += tile.sums[from];
+                self.counts[to] += tile.counts[from];
+            }
+        }
+    }
+
+    /// Adds the samples of another pass over the same image.
+    pub fn merge(&mut self, other: &Film) {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "cannot merge films of different sizes"
+        );
+        self.merge_tile(other, 0, 0);
+    }
+
+    /// Averages every pixel into an image for post-processing and output.
+    pub fn resolve(&self) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                framebuffer.set(x, y, self.pixel(x, y));
+            }
+        }
+        framebuffer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_sample() {
+        let mut film = Film::new(3, 2);
+        film.add_sample(2, 1, Vec3::new(1.0, 2.0, 3.0));
+        film.add_sample(2, 1, Vec3::new(3.0, 2.0, 1.0));
+        assert_eq!(film.sample_count(2, 1), 2);
+        assert_eq!(film.sum(2, 1), Vec3::new(4.0, 4.0, 4.0));
+        assert_eq!(film.pixel(2, 1), Vec3::new(2.0, 2.0, 2.0));
+        assert_eq!(film.sample_count(0, 0), 0);
+        assert_eq!(film.pixel(0, 0), Vec3::new(0.0, 0.0, 0.0));
+
+        let image = film.resolve();
+        assert_eq!(image.get(2, 1), Vec3::new(2.0, 2.0, 2.0));
+        assert_eq!(image.get(1, 1), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut tile = Film::new(2, 2);
+        tile.add_sample(0, 0, Vec3::new(1.0, 1.0, 1.0));
+        tile.add_sample(1, 1, Vec3::new(2.0, 2.0, 2.0));
+
+        let mut film = Film::new(3, 3);
+        film.add_sample(2, 2, Vec3::new(4.0, 4.0, 4.0));
+        film.merge_tile(&tile, 1, 1);
+        assert_eq!(film.pixel(1, 1), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(film.pixel(2, 2), Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(film.sample_count(2, 2), 2);
+
+        // Only the top-left pixel of the tile lands inside the film.
+        film.merge_tile(&tile, 2, 2);
+        assert_eq!(film.sample_count(2, 2), 3);
+
+        let mut total = film.clone();
+        total.merge(&film);
+        assert_eq!(total.sample_count(2, 2), 6);
+        assert_eq!(total.pixel(2, 2), film.pixel(2, 2));
+    }
+}
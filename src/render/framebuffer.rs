@@ -0,0 +1,41 @@
+use crate::structs::vec3::Vec3;
+
+/// Linear RGB image, stored row by row from the top-left pixel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![Vec3::new(0.0, 0.0, 0.0); width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Vec3) {
+        self.pixels[y * self.width + x] = color;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_set() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.set(2, 1, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(fb.get(2, 1), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(fb.pixels[5], Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(fb.get(0, 0), Vec3::new(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,577 @@
+use std::f64;
+
+use crate::materials::{BsdfSample, Scatterable};
+use crate::objects::{HitRecord, Hittable, HittableList};
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Relative difference in distance within which a hit on the lights counts
+/// as the same point as a hit on the world. Shadow rays also leave out this
+/// fraction of the distance at the end, so that they do not hit the light.
+const SHADOW_EPSILON: f64 = 1e-6;
+
+/// Estimates the radiance arriving along a camera ray.
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, r: &Ray, world: &dyn Hittable, sampler: &mut Sampler) -> Vec3;
+}
+
+/// Radiance of rays that leave the scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    Solid(Vec3),
+    /// Blend from `bottom` straight down to `top` straight up.
+    Gradient {
+        bottom: Vec3,
+        top: Vec3,
+    },
+}
+
+impl Background {
+    /// White-to-blue sky used by the book scenes.
+    pub fn sky() -> Background {
+        Background::Gradient {
+            bottom: Vec3::new(1.0, 1.0, 1.0),
+            top: Vec3::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn color(&self, r: &Ray) -> Vec3 {
+        match *self {
+            Background::Solid(color) => color,
+            Background::Gradient { bottom, top } => {
+                let unit_dir: Vec3 = Vec3::unit_vector(&r.direction());
+                let t: f64 = 0.5 * (unit_dir.y() + 1.0);
+                bottom * (1.0 - t) + top * t
+            }
+        }
+    }
+}
+
+/// Brute-force path tracer: follows one scattered ray per bounce until it
+/// leaves the scene, is absorbed, or `max_depth` bounces have been made,
+/// adding up the light emitted by every surface on the way.
+#[derive(Clone, Copy, Debug)]
+pub struct PathTracer {
+    pub max_depth: usize,
+    /// Hits closer than this are ignored so that scattered rays do not hit
+    /// the surface they start on.
+    pub t_min: f64,
+    pub background: Background,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: usize) -> PathTracer {
+        PathTracer {
+            max_depth,
+            t_min: 0.001,
+            background: Background::sky(),
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, r: &Ray, world: &dyn Hittable, sampler: &mut Sampler) -> Vec3 {
+        let mut ray: Ray = *r;
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+        let mut rec = HitRecord::new();
+        for _ in 0..self.max_depth {
+            if !world.sample_collision(&ray, self.t_min, f64::MAX, &mut rec, sampler) {
+                return radiance + throughput * self.background.color(&ray);
+            }
+            radiance += throughput * rec.material.emitted(&ray, &rec);
+            let mut scattered = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+            let mut attenuation = Vec3::new(0.0, 0.0, 0.0);
+            if !rec
+                .material
+                .scatter(&ray, &rec, &mut attenuation, &mut scattered, sampler)
+            {
+                return radiance;
+            }
+            throughput = throughput * attenuation;
+            ray = scattered;
+        }
+        radiance
+    }
+}
+
+/// Path tracer with next-event estimation. At every hit on a surface without
+/// delta lobes, and every scattering point in a medium, it also traces a
+/// shadow ray towards a point sampled on `lights`, and combines that estimate
+/// with the one from the bounce by multiple importance sampling, so small
+/// lights no longer have to be found by chance. Media between the point and
+/// the light dim the shadow ray through `Hittable::transmittance`.
+///
+/// `lights` holds the emitters to sample, usually shared with the world
+/// through `HittableList::push_shared`. Emitters missing from it are still
+/// found by bounces, as in `PathTracer`.
+pub struct NeePathTracer {
+    pub max_depth: usize,
+    pub t_min: f64,
+    pub background: Background,
+    pub lights: HittableList,
+}
+
+impl NeePathTracer {
+    pub fn new(max_depth: usize, lights: HittableList) -> NeePathTracer {
+        NeePathTracer {
+            max_depth,
+            t_min: 0.001,
+            background: Background::sky(),
+            lights,
+        }
+    }
+
+    /// Light leaving a hit along `wo` that arrives directly from a point
+    /// sampled on the lights, weighted against sampling the surface.
+    fn sample_light(
+        &self,
+        rec: &HitRecord,
+        wo: &Vec3,
+        time: f64,
+        world: &dyn Hittable,
+        sampler: &mut Sampler,
+    ) -> Vec3 {
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let direction: Vec3 = match self.lights.random(&rec.p, sampler) {
+            Some(direction) => direction,
+            None => return black,
+        };
+        let light_pdf: f64 = self.lights.pdf_value(&rec.p, &direction);
+        let wi: Vec3 = Vec3::unit_vector(&direction);
+        let f: Vec3 = rec.material.eval(rec, &wi, wo);
+        if light_pdf <= 0.0 || f == black {
+            return black;
+        }
+        let shadow = Ray::with_time(rec.p, direction, time);
+        let mut light_rec = HitRecord::new();
+        if !self
+            .lights
+            .hit(&shadow, self.t_min, f64::MAX, &mut light_rec)
+        {
+            return black;
+        }
+        // Surfaces in front of the light block it and media dim it. The light
+        // itself is part of the world, so stop just short of it.
+        let transmittance: f64 = world.transmittance(
+            &shadow,
+            self.t_min,
+            light_rec.t * (1.0 - SHADOW_EPSILON),
+            sampler,
+        );
+        if transmittance == 0.0 {
+            return black;
+        }
+        let emitted: Vec3 = light_rec.material.emitted(&shadow, &light_rec);
+        let bsdf_pdf: f64 = rec.material.pdf(rec, &wi, wo);
+        f * emitted * (transmittance * power_heuristic(light_pdf, bsdf_pdf) / light_pdf)
+    }
+
+    /// Whether the first of the lights along `ray` lies at `t`, i.e. whether
+    /// `sample_light` could have found the point the ray hit.
+    fn is_light_at(&self, ray: &Ray, t: f64) -> bool {
+        let mut light_rec = HitRecord::new();
+        self.lights.hit(ray, self.t_min, f64::MAX, &mut light_rec)
+            && (light_rec.t - t).abs() <= t * SHADOW_EPSILON
+    }
+}
+
+/// Weight of a sample taken with density `a` against another strategy with density `b`.
+fn power_heuristic(a: f64, b: f64) -> f64 {
+    if a <= 0.0 {
+        return 0.0;
+    }
+    a * a / (a * a + b * b)
+}
+
+impl Integrator for NeePathTracer {
+    fn radiance(&self, r: &Ray, world: &dyn Hittable, sampler: &mut Sampler) -> Vec3 {
+        let mut ray: Ray = *r;
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+        let mut rec = HitRecord::new();
+        // Density of the bounce that produced `ray`; `None` for camera rays and
+        // delta lobes, which could not have sampled the lights.
+        let mut bsdf_pdf: Option<f64> = None;
+        for _ in 0..self.max_depth {
+            if !world.sample_collision(&ray, self.t_min, f64::MAX, &mut rec, sampler) {
+                return radiance + throughput * self.background.color(&ray);
+            }
+            let emitted: Vec3 = rec.material.emitted(&ray, &rec);
+            if emitted != Vec3::new(0.0, 0.0, 0.0) {
+                // Emitters that are not among the lights have only this estimate.
+                let weight: f64 = match bsdf_pdf {
+                    Some(pdf) if self.is_light_at(&ray, rec.t) => {
+                        power_heuristic(pdf, self.lights.pdf_value(&ray.origin(), &ray.direction()))
+                    }
+                    _ => 1.0,
+                };
+                radiance += throughput * emitted * weight;
+            }
+
+            let wo: Vec3 = -Vec3::unit_vector(&ray.direction());
+            let sample: BsdfSample = match rec.material.sample(&rec, &wo, sampler) {
+                Some(sample) => sample,
+                None => return radiance,
+            };
+            if sample.delta {
+                bsdf_pdf = None;
+            } else {
+                radiance += throughput * self.sample_light(&rec, &wo, ray.time(), world, sampler);
+                bsdf_pdf = Some(sample.pdf);
+            }
+            throughput = throughput * sample.weight;
+            ray = Ray::with_time(rec.p, sample.direction, ray.time());
+        }
+        radiance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::materials::isotropic::Isotropic;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::metal::Metal;
+    use crate::materials::Material;
+    use crate::objects::constant_medium::ConstantMedium;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::triangle::Triangle;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_background() {
+        let up = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+        let down = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(Background::sky().color(&up), Vec3::new(0.5, 0.7, 1.0));
+        assert_eq!(Background::sky().color(&down), Vec3::new(1.0, 1.0, 1.0));
+        let black = Background::Solid(Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(black.color(&up), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_path_tracer() {
+        let mut world = HittableList::new();
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, -2.0),
+            1.0,
+            Material::Metal(Metal::new(Vec3::new(0.5, 0.25, 1.0), 0.0)),
+        ));
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, 2.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.0, 0.0, 0.0))),
+        ));
+        let integrator = PathTracer {
+            max_depth: 5,
+            t_min: 0.001,
+            background: Background::Solid(Vec3::new(1.0, 1.0, 1.0)),
+        };
+        let mut sampler = Sampler::new(0);
+
+        let miss = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            integrator.radiance(&miss, &world, &mut sampler),
+            Vec3::new(1.0, 1.0, 1.0)
+        );
+
+        // Reflected by the mirror straight up and out of the scene.
+        let mirror = Ray::new(Vec3::new(0.0, 2.0, -2.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(
+            integrator.radiance(&mirror, &world, &mut sampler),
+            Vec3::new(0.5, 0.25, 1.0)
+        );
+
+        // Absorbed by the black sphere.
+        let black = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            integrator.radiance(&black, &world, &mut sampler),
+            Vec3::new(0.0, 0.0, 0.0)
+        );
+
+        // Without any bounces left nothing reaches the camera.
+        let trapped = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let no_depth = PathTracer {
+            max_depth: 0,
+            ..integrator
+        };
+        assert_eq!(
+            no_depth.radiance(&trapped, &world, &mut sampler),
+            Vec3::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_emission() {
+        // A grey sphere inside a glowing one, with nothing coming from outside.
+        let mut world = HittableList::new();
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            10.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(1.0, 2.0, 4.0))),
+        ));
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, -3.0),
+            1.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        ));
+        let integrator = PathTracer {
+            background: Background::Solid(Vec3::new(0.0, 0.0, 0.0)),
+            ..PathTracer::new(10)
+        };
+        let mut sampler = Sampler::new(0);
+
+        let light = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            integrator.radiance(&light, &world, &mut sampler),
+            Vec3::new(1.0, 2.0, 4.0)
+        );
+
+        // Every bounce off the convex grey sphere ends on the light.
+        let grey = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        for _ in 0..20 {
+            assert_eq!(
+                integrator.radiance(&grey, &world, &mut sampler),
+                Vec3::new(0.5, 1.0, 2.0)
+            );
+        }
+    }
+
+    fn mean_and_variance(
+        integrator: &dyn Integrator,
+        r: &Ray,
+        world: &dyn Hittable,
+        n: usize,
+    ) -> (f64, f64) {
+        let mut sampler = Sampler::new(1);
+        let samples: Vec<f64> = (0..n)
+            .map(|_| integrator.radiance(r, world, &mut sampler).x())
+            .collect();
+        let mean: f64 = samples.iter().sum::<f64>() / n as f64;
+        let variance: f64 = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / n as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_next_event_estimation() {
+        // A grey floor lit by a small sphere straight above the origin.
+        let grey = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            0.25,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0))),
+        ));
+        let mut world = HittableList::new();
+        world.push(Triangle::new(
+            Vec3::new(-10.0, 0.0, -10.0),
+            Vec3::new(-10.0, 0.0, 10.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            grey,
+        ));
+        world.push_shared(Arc::clone(&light));
+        let mut lights = HittableList::new();
+        lights.push_shared(light);
+
+        let black = Background::Solid(Vec3::new(0.0, 0.0, 0.0));
+        let brute_force = PathTracer {
+            background: black,
+            ..PathTracer::new(10)
+        };
+        let nee = NeePathTracer {
+            background: black,
+            ..NeePathTracer::new(10, lights)
+        };
+        let without_lights = NeePathTracer {
+            background: black,
+            ..NeePathTracer::new(10, HittableList::new())
+        };
+
+        // The light subtends sin^2 = 1/16 of the cosine-weighted hemisphere, so
+        // the origin reflects 0.5 * 4 / 16 towards the viewer.
+        let expected: f64 = 0.125;
+        let r = Ray::new(Vec3::new(0.0, 0.5, 1.0), Vec3::new(0.0, -0.5, -1.0));
+        let (mean, variance) = mean_and_variance(&brute_force, &r, &world, 100_000);
+        assert!((mean - expected).abs() < 0.01, "{}", mean);
+        let (nee_mean, nee_variance) = mean_and_variance(&nee, &r, &world, 20_000);
+        assert!((nee_mean - expected).abs() < 0.002, "{}", nee_mean);
+        assert!(nee_variance * 100.0 < variance);
+        let (unlit_mean, _) = mean_and_variance(&without_lights, &r, &world, 100_000);
+        assert!((unlit_mean - expected).abs() < 0.01, "{}", unlit_mean);
+
+        // The light seen directly is not weighted down.
+        let at_light = Ray::new(Vec3::new(0.0, 0.5, 1.0), Vec3::new(0.0, 0.5, -1.0));
+        let mut sampler = Sampler::new(0);
+        assert_eq!(
+            nee.radiance(&at_light, &world, &mut sampler),
+            Vec3::new(4.0, 4.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_emitter_outside_lights() {
+        // The floor is lit by a sphere missing from the lights, with a dark
+        // light hidden right behind it.
+        let grey = Material::Lambertian(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+        let hidden: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 3.0, 0.0),
+            0.25,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(0.0, 0.0, 0.0))),
+        ));
+        let mut world = HittableList::new();
+        world.push(Triangle::new(
+            Vec3::new(-10.0, 0.0, -10.0),
+            Vec3::new(-10.0, 0.0, 10.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            grey,
+        ));
+        world.push(Sphere::new(
+            Vec3::new(0.0, 1.0, 0.0),
+            0.25,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(4.0, 4.0, 4.0))),
+        ));
+        world.push_shared(Arc::clone(&hidden));
+        let mut lights = HittableList::new();
+        lights.push_shared(hidden);
+
+        // As in `test_next_event_estimation`, the bounces alone find 0.125.
+        let nee = NeePathTracer {
+            background: Background::Solid(Vec3::new(0.0, 0.0, 0.0)),
+            ..NeePathTracer::new(10, lights)
+        };
+        let r = Ray::new(Vec3::new(0.0, 0.5, 1.0), Vec3::new(0.0, -0.5, -1.0));
+        let (mean, _) = mean_and_variance(&nee, &r, &world, 100_000);
+        assert!((mean - 0.125).abs() < 0.01, "{}", mean);
+    }
+
+    #[test]
+    fn test_absorbing_medium() {
+        // A light seen through a black medium of optical depth 1 in front of it.
+        let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, -5.0),
+            1.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(2.0, 2.0, 2.0))),
+        ));
+        let black = Material::Isotropic(Isotropic::new(Vec3::new(0.0, 0.0, 0.0)));
+        let slab: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -2.0), 1.0, black.clone()));
+        let mut world = HittableList::new();
+        world.push_shared(Arc::clone(&light));
+        world.push(ConstantMedium::new(slab, 0.5, black));
+        let mut lights = HittableList::new();
+        lights.push_shared(light);
+
+        let background = Background::Solid(Vec3::new(0.0, 0.0, 0.0));
+        let brute_force = PathTracer {
+            background,
+            ..PathTracer::new(10)
+        };
+        let nee = NeePathTracer {
+            background,
+            ..NeePathTracer::new(10, lights)
+        };
+        let expected: f64 = 2.0 * (-1.0f64).exp();
+        let mut sampler = Sampler::new(0);
+        for integrator in [&brute_force as &dyn Integrator, &nee].iter() {
+            let n: usize = 20000;
+            let mut total: f64 = 0.0;
+            let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+            for _ in 0..n {
+                total += integrator.radiance(&r, &world, &mut sampler).x();
+            }
+            let mean: f64 = total / n as f64;
+            assert!((mean - expected).abs() < 0.02, "{}", mean);
+        }
+    }
+
+    #[test]
+    fn test_two_media() {
+        // Two black media one behind the other, of optical depths 1 and 0.6,
+        // have to dim the light by both.
+        let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, -5.0),
+            1.0,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(2.0, 2.0, 2.0))),
+        ));
+        let black = Material::Isotropic(Isotropic::new(Vec3::new(0.0, 0.0, 0.0)));
+        let near: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -1.5), 0.5, black.clone()));
+        let far: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -3.0), 0.75, black.clone()));
+        let mut world = HittableList::new();
+        world.push_shared(Arc::clone(&light));
+        world.push(ConstantMedium::new(near, 1.0, black.clone()));
+        world.push(ConstantMedium::new(far, 0.4, black));
+        let mut lights = HittableList::new();
+        lights.push_shared(light);
+
+        let background = Background::Solid(Vec3::new(0.0, 0.0, 0.0));
+        let brute_force = PathTracer {
+            background,
+            ..PathTracer::new(10)
+        };
+        let nee = NeePathTracer {
+            background,
+            ..NeePathTracer::new(10, lights)
+        };
+        let expected: f64 = 2.0 * (-1.0 - 0.4 * 1.5f64).exp();
+        let mut sampler = Sampler::new(0);
+        let r = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        for integrator in [&brute_force as &dyn Integrator, &nee].iter() {
+            let n: usize = 20000;
+            let total: f64 = (0..n)
+                .map(|_| integrator.radiance(&r, &world, &mut sampler).x())
+                .sum();
+            let mean: f64 = total / n as f64;
+            assert!((mean - expected).abs() < 0.02, "{}", mean);
+        }
+    }
+
+    #[test]
+    fn test_scattering_medium() {
+        // A white cloud lit by a small light, seen from outside. Shadow rays
+        // from inside the cloud are dimmed by it, so next-event estimation
+        // has to agree with plain bounces.
+        let light: Arc<dyn Hittable> = Arc::new(Sphere::new(
+            Vec3::new(0.0, 3.0, 0.0),
+            0.5,
+            Material::DiffuseLight(DiffuseLight::new(Vec3::new(8.0, 8.0, 8.0))),
+        ));
+        let white = Material::Isotropic(Isotropic::new(Vec3::new(0.9, 0.9, 0.9)));
+        let boundary: Arc<dyn Hittable> =
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, white.clone()));
+        let mut world = HittableList::new();
+        world.push_shared(Arc::clone(&light));
+        world.push(ConstantMedium::new(boundary, 2.0, white));
+        let mut lights = HittableList::new();
+        lights.push_shared(light);
+
+        let background = Background::Solid(Vec3::new(0.0, 0.0, 0.0));
+        let brute_force = PathTracer {
+            background,
+            ..PathTracer::new(20)
+        };
+        let nee = NeePathTracer {
+            background,
+            ..NeePathTracer::new(20, lights)
+        };
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let estimate = |integrator: &dyn Integrator, n: usize| -> f64 {
+            let mut sampler = Sampler::new(2);
+            let total: f64 = (0..n)
+                .map(|_| integrator.radiance(&r, &world, &mut sampler).x())
+                .sum();
+            total / n as f64
+        };
+        let expected: f64 = estimate(&brute_force, 200_000);
+        let mean: f64 = estimate(&nee, 20_000);
+        assert!(expected > 0.01, "{}", expected);
+        assert!(
+            (mean - expected).abs() < 0.05 * expected,
+            "{} {}",
+            mean,
+            expected
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use self::film::Film;
+use self::framebuffer::Framebuffer;
+use self::integrator::Integrator;
+use self::tiles::TileRenderer;
+use crate::objects::camera::Camera;
+use crate::objects::Hittable;
+
+pub mod aov;
+pub mod film;
+pub mod framebuffer;
+pub mod integrator;
+pub mod tiles;
+pub mod tonemap;
+
+/// Image size and sampling parameters of a render.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: usize,
+    /// Index of the first sample per pixel, see `TileRenderer::first_sample`.
+    pub first_sample: usize,
+    pub seed: u64,
+    pub tile_size: usize,
+    /// Number of worker threads; 0 uses one per logical CPU.
+    pub threads: usize,
+}
+
+impl RenderSettings {
+    pub fn new(width: usize, height: usize) -> RenderSettings {
+        RenderSettings {
+            width,
+            height,
+            samples_per_pixel: 10,
+            first_sample: 0,
+            seed: 0,
+            tile_size: 32,
+            threads: 0,
+        }
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+/// Renders a world as seen from a camera with the given integrator.
+pub struct Renderer {
+    pub settings: RenderSettings,
+    integrator: Box<dyn Integrator>,
+}
+
+impl Renderer {
+    pub fn new<I: Integrator + 'static>(settings: RenderSettings, integrator: I) -> Renderer {
+        Renderer {
+            settings,
+            integrator: Box::new(integrator),
+        }
+    }
+
+    pub fn render(&self, world: &dyn Hittable, camera: &Camera) -> Framebuffer {
+        self.render_film(world, camera).resolve()
+    }
+
+    /// Renders the per-pixel sample sums, e.g. to merge with further passes.
+    pub fn render_film(&self, world: &dyn Hittable, camera: &Camera) -> Film {
+        TileRenderer::new(self.settings.width, self.settings.height)
+            .samples_per_pixel(self.settings.samples_per_pixel)
+            .first_sample(self.settings.first_sample)
+            .seed(self.settings.seed)
+            .tile_size(self.settings.tile_size)
+            .threads(self.settings.threads)
+            .render_film(camera, |r, sampler| {
+                self.integrator.radiance(r, world, sampler)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::integrator::{Background, PathTracer};
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::Material;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::HittableList;
+    use crate::structs::vec3::Vec3;
+
+    #[test]
+    fn test_render() {
+        let mut world = HittableList::new();
+        world.push(Sphere::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.0, 0.0, 0.0))),
+        ));
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            2.0,
+            0.0,
+            1.0,
+        );
+        let mut settings = RenderSettings::new(8, 4);
+        settings.samples_per_pixel = 4;
+        let integrator = PathTracer {
+            background: Background::Solid(Vec3::new(1.0, 1.0, 1.0)),
+            ..PathTracer::new(10)
+        };
+        let image = Renderer::new(settings, integrator).render(&world, &camera);
+
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(image.get(4, 2), Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(image.get(0, 0), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            image,
+            Renderer::new(settings, integrator).render(&world, &camera)
+        );
+    }
+}
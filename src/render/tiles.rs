@@ -0,0 +1,255 @@
+use rand::prelude::*;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use super::film::Film;
+use super::framebuffer::Framebuffer;
+use crate::objects::camera::Camera;
+use crate::structs::ray::Ray;
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+/// Rectangle of pixels `[x0, x1) x [y0, y1)`, with `y` counted from the top row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+/// Renders an image in square tiles on a work-stealing thread pool.
+///
+/// Each pixel sample draws from its own `Sampler::for_pixel` stream, so the
+/// image is the same for every tile size and thread count.
+#[derive(Clone, Copy, Debug)]
+pub struct TileRenderer {
+    width: usize,
+    height: usize,
+    samples_per_pixel: usize,
+    first_sample: usize,
+    seed: u64,
+    tile_size: usize,
+    threads: usize,
+}
+
+impl TileRenderer {
+    pub fn new(width: usize, height: usize) -> TileRenderer {
+        TileRenderer {
+            width,
+            height,
+            samples_per_pixel: 1,
+            first_sample: 0,
+            seed: 0,
+            tile_size: 32,
+            threads: 0,
+        }
+    }
+
+    pub fn samples_per_pixel(mut self, samples_per_pixel: usize) -> TileRenderer {
+        self.samples_per_pixel = samples_per_pixel.max(1);
+        self
+    }
+
+    /// Index of the first sample taken per pixel. Passes that start where the
+    /// previous one stopped draw fresh samples and can be merged into one film.
+    pub fn first_sample(mut self, first_sample: usize) -> TileRenderer {
+        self.first_sample = first_sample;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> TileRenderer {
+        self.seed = seed;
+        self
+    }
+
+    pub fn tile_size(mut self, tile_size: usize) -> TileRenderer {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// Number of worker threads; 0 uses one per logical CPU.
+    pub fn threads(mut self, threads: usize) -> TileRenderer {
+        self.threads = threads;
+        self
+    }
+
+    pub fn tiles(&self) -> Vec<Tile> {
+        let mut tiles: Vec<Tile> = Vec::new();
+        for y0 in (0..self.height).step_by(self.tile_size) {
+            for x0 in (0..self.width).step_by(self.tile_size) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + self.tile_size).min(self.width),
+                    y1: (y0 + self.tile_size).min(self.height),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Averages `samples_per_pixel` values of `radiance` along jittered camera rays
+    /// through every pixel.
+    pub fn render<F>(&self, camera: &Camera, radiance: F) -> Framebuffer
+    where
+        F: Fn(&Ray, &mut Sampler) -> Vec3 + Sync,
+    {
+        self.render_film(camera, radiance).resolve()
+    }
+
+    /// Like `render`, but keeps the per-pixel sums and sample counts.
+    pub fn render_film<F>(&self, camera: &Camera, radiance: F) -> Film
+    where
+        F: Fn(&Ray, &mut Sampler) -> Vec3 + Sync,
+    {
+        let render_tile = |tile: &Tile| -> Film { self.render_tile(tile, camera, &radiance) };
+        let tiles: Vec<Tile> = self.tiles();
+        let rendered: Vec<Film> = if self.threads == 1 {
+            tiles.iter().map(render_tile).collect()
+        } else {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .build()
+                .expect("failed to start render threads");
+            pool.install(|| tiles.par_iter().map(render_tile).collect())
+        };
+
+        let mut film = Film::new(self.width, self.height);
+        for (tile, tile_film) in tiles.iter().zip(rendered.iter()) {
+            film.merge_tile(tile_film, tile.x0, tile.y0);
+        }
+        film
+    }
+
+    fn render_tile<F>(&self, tile: &Tile, camera: &Camera, radiance: &F) -> Film
+    where
+        F: Fn(&Ray, &mut Sampler) -> Vec3,
+    {
+        let mut film = Film::new(tile.x1 - tile.x0, tile.y1 - tile.y0);
+        for y in tile.y0..tile.y1 {
+            // Camera coordinates grow upwards, image rows downwards.
+            let j: usize = self.height - 1 - y;
+            for i in tile.x0..tile.x1 {
+                for s in self.first_sample..self.first_sample + self.samples_per_pixel {
+                    let mut sampler = Sampler::for_pixel(self.seed, i, j, s);
+                    let u: f64 = (i as f64 + sampler.gen::<f64>()) / self.width as f64;
+                    let v: f64 = (j as f64 + sampler.gen::<f64>()) / self.height as f64;
+                    let r: Ray = camera.get_ray(u, v, &mut sampler);
+                    film.add_sample(i - tile.x0, y - tile.y0, radiance(&r, &mut sampler));
+                }
+            }
+        }
+        film
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::materials::dielectric::Dielectric;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::{Material, Scatterable};
+    use crate::objects::bvh::BvhNode;
+    use crate::objects::sphere::Sphere;
+    use crate::objects::{HitRecord, Hittable, HittableList};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<Camera>();
+        assert_send_sync::<Material>();
+        assert_send_sync::<HittableList>();
+        assert_send_sync::<BvhNode>();
+    }
+
+    fn color(r: &Ray, world: &dyn Hittable, depth: usize, sampler: &mut Sampler) -> Vec3 {
+        let mut rec = HitRecord::new();
+        if depth == 0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        if world.hit(r, 0.001, f64::MAX, &mut rec) {
+            let mut scattered = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+            let mut attenuation = Vec3::new(0.0, 0.0, 0.0);
+            if rec
+                .material
+                .scatter(r, &rec, &mut attenuation, &mut scattered, sampler)
+            {
+                return attenuation * color(&scattered, world, depth - 1, sampler);
+            }
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let t: f64 = 0.5 * (Vec3::unit_vector(&r.direction()).y() + 1.0);
+        Vec3::new(1.0, 1.0, 1.0) * (1.0 - t) + Vec3::new(0.5, 0.7, 1.0) * t
+    }
+
+    #[test]
+    fn test_tiles_cover_image() {
+        let tiles = TileRenderer::new(10, 7).tile_size(4).tiles();
+        assert_eq!(tiles.len(), 6);
+        let area: usize = tiles.iter().map(|t| (t.x1 - t.x0) * (t.y1 - t.y0)).sum();
+        assert_eq!(area, 70);
+        assert_eq!(
+            tiles[5],
+            Tile {
+                x0: 8,
+                y0: 4,
+                x1: 10,
+                y1: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_same_image_for_any_threading() {
+        let mut list = HittableList::new();
+        list.push(Sphere::new(
+            Vec3::new(0.0, -100.5, -1.0),
+            100.0,
+            Material::Lambertian(Lambertian::new(Vec3::new(0.8, 0.8, 0.0))),
+        ));
+        list.push(Sphere::new(
+            Vec3::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Dielectric(Dielectric::new(1.5)),
+        ));
+        let world = BvhNode::new(list);
+        let camera = Camera::new(
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            60.0,
+            1.5,
+            0.1,
+            2.0,
+        );
+        let renderer = TileRenderer::new(24, 16).samples_per_pixel(4).seed(9);
+        let radiance = |r: &Ray, sampler: &mut Sampler| color(r, &world, 10, sampler);
+
+        let reference = renderer
+            .threads(1)
+            .tile_size(1000)
+            .render(&camera, radiance);
+        for (threads, tile_size) in [(1, 5), (2, 7), (4, 1), (0, 16)].iter() {
+            let image = renderer
+                .threads(*threads)
+                .tile_size(*tile_size)
+                .render(&camera, radiance);
+            assert_eq!(image, reference);
+        }
+
+        let reseeded = renderer.seed(10).render(&camera, radiance);
+        assert_ne!(reseeded, reference);
+
+        // Two passes of two samples each take the same samples as one pass of four.
+        let half = renderer.samples_per_pixel(2);
+        let mut film = half.render_film(&camera, radiance);
+        film.merge(&half.first_sample(2).render_film(&camera, radiance));
+        let merged = film.resolve();
+        assert_eq!(film.sample_count(3, 5), 4);
+        for (a, b) in merged.pixels.iter().zip(reference.pixels.iter()) {
+            assert!((*a - *b).length() < 1e-12);
+        }
+    }
+}
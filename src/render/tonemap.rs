@@ -0,0 +1,173 @@
+use crate::render::framebuffer::Framebuffer;
+use crate::structs::vec3::Vec3;
+
+/// Curve that maps linear radiance to display values in [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneOperator {
+    Clamp,
+    /// `c / (1 + c)`.
+    Reinhard,
+    /// Reinhard curve that reaches 1 at `white` instead of at infinity.
+    ExtendedReinhard {
+        white: f64,
+    },
+    /// John Hable's filmic curve from Uncharted 2.
+    Hable,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneOperator {
+    pub fn map(&self, c: f64) -> f64 {
+        let c: f64 = c.max(0.0);
+        let mapped: f64 = match *self {
+            ToneOperator::Clamp => c,
+            ToneOperator::Reinhard => c / (1.0 + c),
+            ToneOperator::ExtendedReinhard { white } => c * (1.0 + c / (white * white)) / (1.0 + c),
+            ToneOperator::Hable => {
+                let exposure_bias: f64 = 2.0;
+                let white: f64 = 11.2;
+                hable_partial(c * exposure_bias) / hable_partial(white)
+            }
+            ToneOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        };
+        mapped.min(1.0)
+    }
+}
+
+fn hable_partial(x: f64) -> f64 {
+    let (a, b, c, d, e, f): (f64, f64, f64, f64, f64, f64) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+}
+
+/// Exposure and tone curve applied to a linear render before it is encoded
+/// into an 8-bit format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapper {
+    /// Exposure in stops; every +1 doubles the radiance.
+    pub exposure: f64,
+    pub operator: ToneOperator,
+}
+
+impl Default for ToneMapper {
+    fn default() -> ToneMapper {
+        ToneMapper::new(ToneOperator::Clamp)
+    }
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneOperator) -> ToneMapper {
+        ToneMapper {
+            exposure: 0.0,
+            operator,
+        }
+    }
+
+    pub fn exposure(mut self, exposure: f64) -> ToneMapper {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Display-linear color of `color`, each channel in [0, 1].
+    pub fn map(&self, color: Vec3) -> Vec3 {
+        let scaled: Vec3 = color * 2f64.powf(self.exposure);
+        Vec3::new(
+            self.operator.map(scaled.x()),
+            self.operator.map(scaled.y()),
+            self.operator.map(scaled.z()),
+        )
+    }
+
+    pub fn apply(&self, image: &Framebuffer) -> Framebuffer {
+        Framebuffer {
+            width: image.width,
+            height: image.height,
+            pixels: image.pixels.iter().map(|c| self.map(*c)).collect(),
+        }
+    }
+}
+
+/// Piecewise sRGB transfer function from display-linear to encoded values.
+pub fn srgb_encode(c: f64) -> f64 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `srgb_encode`, e.g. for colors read from 8-bit images.
+pub fn srgb_decode(c: f64) -> f64 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_operators() {
+        let operators = [
+            ToneOperator::Clamp,
+            ToneOperator::Reinhard,
+            ToneOperator::ExtendedReinhard { white: 4.0 },
+            ToneOperator::Hable,
+            ToneOperator::Aces,
+        ];
+        for op in operators.iter() {
+            assert_eq!(op.map(-1.0), op.map(0.0));
+            assert!(op.map(0.0).abs() < 1e-9);
+            assert!(op.map(1.0e6) <= 1.0);
+            // Monotonic on a range of radiances.
+            let mut previous: f64 = op.map(0.0);
+            for i in 1..100 {
+                let mapped: f64 = op.map(i as f64 * 0.1);
+                assert!(mapped >= previous);
+                previous = mapped;
+            }
+        }
+        assert_eq!(ToneOperator::Clamp.map(0.5), 0.5);
+        assert_eq!(ToneOperator::Clamp.map(3.0), 1.0);
+        assert_close(ToneOperator::Reinhard.map(1.0), 0.5);
+        assert_close(ToneOperator::ExtendedReinhard { white: 4.0 }.map(4.0), 1.0);
+        assert_close(ToneOperator::Hable.map(11.2 / 2.0), 1.0);
+        assert_close(ToneOperator::Aces.map(1.0), 0.803_797);
+    }
+
+    #[test]
+    fn test_exposure() {
+        let tone = ToneMapper::new(ToneOperator::Reinhard).exposure(1.0);
+        assert_eq!(
+            tone.map(Vec3::new(0.5, 0.0, 1.5)),
+            Vec3::new(0.5, 0.0, 0.75)
+        );
+
+        let mut image = Framebuffer::new(2, 1);
+        image.set(0, 0, Vec3::new(0.25, 0.5, 2.0));
+        let mapped = ToneMapper::default().exposure(1.0).apply(&image);
+        assert_eq!(mapped.get(0, 0), Vec3::new(0.5, 1.0, 1.0));
+        assert_eq!(mapped.get(1, 0), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_srgb_encode() {
+        assert_eq!(srgb_encode(0.0), 0.0);
+        assert_close(srgb_encode(1.0), 1.0);
+        assert_close(srgb_encode(0.002), 0.025_84);
+        assert_close(srgb_encode(0.5), 0.735_357);
+        for i in 0..=20 {
+            let c: f64 = i as f64 / 20.0;
+            assert_close(srgb_decode(srgb_encode(c)), c);
+        }
+        // The two pieces meet at the threshold.
+        assert!((srgb_encode(0.003_130_8) - srgb_encode(0.003_130_81)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,134 @@
+use std::f64;
+
+use crate::structs::ray::Ray;
+use crate::structs::vec3::Vec3;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Box that contains nothing; surrounding it with any other box yields that box.
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Vec3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn min(&self) -> Vec3 {
+        self.min
+    }
+
+    pub fn max(&self) -> Vec3 {
+        self.max
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Surface area of the box; zero for an empty box.
+    pub fn surface_area(&self) -> f64 {
+        let d: Vec3 = self.max - self.min;
+        if d.x() < 0.0 || d.y() < 0.0 || d.z() < 0.0 {
+            return 0.0;
+        }
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    /// Index of the axis along which the box is widest.
+    pub fn longest_axis(&self) -> usize {
+        let extent: Vec3 = self.max - self.min;
+        if extent.x() > extent.y() && extent.x() > extent.z() {
+            0
+        } else if extent.y() > extent.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(r, t_min, t_max).is_some()
+    }
+
+    /// Range of the ray parameter, within `t_min` and `t_max`, for which `r`
+    /// is inside the box.
+    pub fn clip(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for a in 0..3 {
+            let inv_d: f64 = 1.0 / r.direction()[a];
+            let mut t0: f64 = (self.min[a] - r.origin()[a]) * inv_d;
+            let mut t1: f64 = (self.max[a] - r.origin()[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::new(
+                box0.min.x().min(box1.min.x()),
+                box0.min.y().min(box1.min.y()),
+                box0.min.z().min(box1.min.z()),
+            ),
+            max: Vec3::new(
+                box0.max.x().max(box1.max.x()),
+                box0.max.y().max(box1.max.y()),
+                box0.max.z().max(box1.max.z()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hit() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let towards = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        let away = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, -1.0));
+        let beside = Ray::new(Vec3::new(2.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert!(aabb.hit(&towards, 0.0, f64::MAX));
+        assert!(!aabb.hit(&towards, 0.0, 3.0));
+        assert!(!aabb.hit(&away, 0.0, f64::MAX));
+        assert!(!aabb.hit(&beside, 0.0, f64::MAX));
+    }
+
+    #[test]
+    fn test_surrounding_box() {
+        let box0 = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let box1 = Aabb::new(Vec3::new(-1.0, 0.5, 0.0), Vec3::new(0.5, 2.0, 3.0));
+        assert_eq!(
+            Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 3.0)),
+            Aabb::surrounding_box(&box0, &box1)
+        );
+        assert_eq!(box0, Aabb::surrounding_box(&box0, &Aabb::empty()));
+    }
+
+    #[test]
+    fn test_surface_area() {
+        let aabb = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(aabb.surface_area(), 22.0);
+        assert_eq!(Aabb::empty().surface_area(), 0.0);
+    }
+}
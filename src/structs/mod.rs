@@ -1,2 +1,7 @@
+pub mod aabb;
+pub mod onb;
 pub mod ray;
+pub mod sampler;
+pub mod transform;
 pub mod vec3;
+pub mod voxel_grid;
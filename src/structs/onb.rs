@@ -0,0 +1,55 @@
+use crate::structs::vec3::Vec3;
+
+/// Orthonormal basis with `w` along a given direction, used to turn directions
+/// sampled around the z axis into world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: &Vec3) -> Onb {
+        let w: Vec3 = Vec3::unit_vector(n);
+        let a: Vec3 = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v: Vec3 = Vec3::unit_vector(&Vec3::cross(&w, &a));
+        let u: Vec3 = Vec3::cross(&v, &w);
+        Onb { u, v, w }
+    }
+
+    /// World-space vector with coordinates `a` in this basis.
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        self.u * a.x() + self.v * a.y() + self.w * a.z()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_w() {
+        for n in [
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.3, 0.5, 0.8),
+        ]
+        .iter()
+        {
+            let onb = Onb::from_w(n);
+            assert!((onb.w - Vec3::unit_vector(n)).length() < 1e-12);
+            assert!(Vec3::dot(&onb.u, &onb.v).abs() < 1e-12);
+            assert!(Vec3::dot(&onb.u, &onb.w).abs() < 1e-12);
+            assert!((onb.u.length() - 1.0).abs() < 1e-12);
+            assert!((onb.v.length() - 1.0).abs() < 1e-12);
+            // Right-handed.
+            assert!((Vec3::cross(&onb.u, &onb.v) - onb.w).length() < 1e-12);
+            assert!((onb.local(&Vec3::new(0.0, 0.0, 1.0)) - onb.w).length() < 1e-12);
+        }
+    }
+}
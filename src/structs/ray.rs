@@ -4,11 +4,18 @@ use crate::structs::vec3::Vec3;
 pub struct Ray {
     pub a: Vec3,
     pub b: Vec3,
+    /// Instant within the camera shutter the ray travels at, which moving
+    /// objects are placed for.
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(a: Vec3, b: Vec3) -> Ray {
-        Ray { a: a, b: b }
+        Ray::with_time(a, b, 0.0)
+    }
+
+    pub fn with_time(a: Vec3, b: Vec3, time: f64) -> Ray {
+        Ray { a, b, time }
     }
 
     pub fn origin(&self) -> Vec3 {
@@ -19,6 +26,10 @@ impl Ray {
         self.b
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn point_at_parameter(&self, t: f64) -> Vec3 {
         self.a + self.b * t
     }
@@ -40,7 +51,7 @@ mod test {
             d1: 1.0,
             d2: 0.0,
         };
-        let ray = Ray { a: orig, b: dir };
+        let ray = Ray::new(orig, dir);
         assert_eq!(orig, ray.origin())
     }
 
@@ -56,7 +67,7 @@ mod test {
             d1: 1.0,
             d2: 0.0,
         };
-        let ray = Ray { a: orig, b: dir };
+        let ray = Ray::new(orig, dir);
         assert_eq!(dir, ray.direction())
     }
 
@@ -73,7 +84,7 @@ mod test {
             d1: 1.0,
             d2: 0.0,
         };
-        let ray = Ray { a: orig, b: dir };
+        let ray = Ray::new(orig, dir);
         assert_eq!(
             Vec3 {
                 d0: 3.5,
@@ -83,4 +94,14 @@ mod test {
             ray.point_at_parameter(f)
         )
     }
+
+    #[test]
+    fn test_time() {
+        let orig = Vec3::new(1.0, 1.0, 1.0);
+        let dir = Vec3::new(1.0, 1.0, 0.0);
+        assert_eq!(Ray::new(orig, dir).time(), 0.0);
+        let ray = Ray::with_time(orig, dir, 0.25);
+        assert_eq!(ray.time(), 0.25);
+        assert_eq!(ray.point_at_parameter(1.0), Vec3::new(2.0, 2.0, 1.0));
+    }
 }
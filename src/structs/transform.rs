@@ -0,0 +1,347 @@
+use std::ops;
+
+use crate::structs::aabb::Aabb;
+use crate::structs::vec3::Vec3;
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix4 {
+        Matrix4 { m }
+    }
+
+    pub fn identity() -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Matrix4 { m }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Matrix4 { m }
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, or `None`
+    /// if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a: [[f64; 4]; 4] = self.m;
+        let mut inv: [[f64; 4]; 4] = Matrix4::identity().m;
+        for column in 0..4 {
+            let pivot: usize = (column..4)
+                .max_by(|i, j| {
+                    a[*i][column]
+                        .abs()
+                        .partial_cmp(&a[*j][column].abs())
+                        .unwrap()
+                })
+                .unwrap();
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inv.swap(column, pivot);
+            let scale: f64 = 1.0 / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= scale;
+                inv[column][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == column {
+                    continue;
+                }
+                let factor: f64 = a[row][column];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[column][j];
+                    inv[row][j] -= factor * inv[column][j];
+                }
+            }
+        }
+        Some(Matrix4 { m: inv })
+    }
+
+    /// Determinant of the upper 3x3 part, the volume scale of the linear map.
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Applies the matrix to `p` as a point, with w = 1.
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let m = &self.m;
+        let x: f64 = m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3];
+        let y: f64 = m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3];
+        let z: f64 = m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3];
+        let w: f64 = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x, y, z) / w
+        }
+    }
+
+    /// Applies the upper 3x3 part of the matrix to `v` as a direction, with w = 0.
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, other: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+/// Affine transform kept together with its inverse, so that neither has to be
+/// recomputed per ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+}
+
+impl Transform {
+    /// Panics if `matrix` is singular.
+    pub fn new(matrix: Matrix4) -> Transform {
+        let inverse: Matrix4 = matrix.inverse().expect("transform is not invertible");
+        Transform { matrix, inverse }
+    }
+
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    pub fn translate(offset: Vec3) -> Transform {
+        let mut matrix = Matrix4::identity();
+        let mut inverse = Matrix4::identity();
+        for axis in 0..3 {
+            matrix.m[axis][3] = offset[axis];
+            inverse.m[axis][3] = -offset[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Scales by `factors` along the axes. Panics if a factor is zero.
+    pub fn scale(factors: Vec3) -> Transform {
+        let mut matrix = Matrix4::identity();
+        let mut inverse = Matrix4::identity();
+        for axis in 0..3 {
+            assert!(factors[axis] != 0.0, "cannot scale by zero");
+            matrix.m[axis][axis] = factors[axis];
+            inverse.m[axis][axis] = 1.0 / factors[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Rotates by `degrees` counter-clockwise around `axis`, looking down the axis.
+    pub fn rotate(axis: Vec3, degrees: f64) -> Transform {
+        let a: Vec3 = Vec3::unit_vector(&axis);
+        let (sin, cos): (f64, f64) = degrees.to_radians().sin_cos();
+        let mut matrix = Matrix4::identity();
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix.m[i][j] = a[i] * a[j] * (1.0 - cos) + if i == j { cos } else { 0.0 };
+            }
+        }
+        matrix.m[0][1] -= a.z() * sin;
+        matrix.m[0][2] += a.y() * sin;
+        matrix.m[1][0] += a.z() * sin;
+        matrix.m[1][2] -= a.x() * sin;
+        matrix.m[2][0] -= a.y() * sin;
+        matrix.m[2][1] += a.x() * sin;
+        // Rotations are orthogonal.
+        Transform {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn rotate_x(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(1.0, 0.0, 0.0), degrees)
+    }
+
+    pub fn rotate_y(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn rotate_z(degrees: f64) -> Transform {
+        Transform::rotate(Vec3::new(0.0, 0.0, 1.0), degrees)
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverted(&self) -> Transform {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: &Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    /// Transforms a surface normal with the inverse transpose, which keeps it
+    /// perpendicular to the transformed surface. The result is not normalized.
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        // The transpose is applied by reading the inverse by columns.
+        let m = &self.inverse.m;
+        Vec3::new(
+            m[0][0] * n.x() + m[1][0] * n.y() + m[2][0] * n.z(),
+            m[0][1] * n.x() + m[1][1] * n.y() + m[2][1] * n.z(),
+            m[0][2] * n.x() + m[1][2] * n.y() + m[2][2] * n.z(),
+        )
+    }
+
+    /// Box enclosing the transformed corners of `bbox`.
+    pub fn bounding_box(&self, bbox: &Aabb) -> Aabb {
+        let mut output = Aabb::empty();
+        for corner in 0..8 {
+            let p: Vec3 = Vec3::new(
+                if corner & 1 == 0 {
+                    bbox.min.x()
+                } else {
+                    bbox.max.x()
+                },
+                if corner & 2 == 0 {
+                    bbox.min.y()
+                } else {
+                    bbox.max.y()
+                },
+                if corner & 4 == 0 {
+                    bbox.min.z()
+                } else {
+                    bbox.max.z()
+                },
+            );
+            let q: Vec3 = self.point(&p);
+            output = Aabb::surrounding_box(&output, &Aabb::new(q, q));
+        }
+        output
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Matrix4::new([
+            [2.0, 0.0, 1.0, 3.0],
+            [1.0, 3.0, 0.0, -1.0],
+            [0.0, 1.0, 4.0, 2.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product: Matrix4 = m * m.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected: f64 = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-12);
+            }
+        }
+        assert_eq!(m.determinant3(), 25.0);
+        let mut singular = Matrix4::identity();
+        singular.m[2][2] = 0.0;
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let p = Vec3::new(1.0, 2.0, 3.0);
+        let translate = Transform::translate(Vec3::new(1.0, -1.0, 0.5));
+        assert_eq!(translate.point(&p), Vec3::new(2.0, 1.0, 3.5));
+        assert_eq!(translate.vector(&p), p);
+
+        let rotate = Transform::rotate_z(90.0);
+        assert_close(
+            rotate.point(&Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert_close(
+            Transform::rotate_y(90.0).point(&Vec3::new(0.0, 0.0, 1.0)),
+            Vec3::new(1.0, 0.0, 0.0),
+        );
+
+        // Scale, then rotate, then translate.
+        let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .then(&rotate)
+            .then(&translate);
+        assert_close(t.point(&Vec3::new(1.0, 0.0, 0.0)), Vec3::new(1.0, 1.0, 0.5));
+        assert_close(t.inverted().point(&t.point(&p)), p);
+        let rebuilt = Transform::new(t.matrix);
+        assert_close(rebuilt.inverse.transform_point(&p), t.inverted().point(&p));
+    }
+
+    #[test]
+    fn test_normal() {
+        // Squashing a 45 degree plane along y tilts its normal towards y.
+        let squash = Transform::scale(Vec3::new(1.0, 0.5, 1.0));
+        let tangent: Vec3 = squash.vector(&Vec3::new(1.0, -1.0, 0.0));
+        let normal: Vec3 = squash.normal(&Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(Vec3::dot(&tangent, &normal), 0.0);
+        assert_close(
+            Vec3::unit_vector(&normal),
+            Vec3::unit_vector(&Vec3::new(1.0, 2.0, 0.0)),
+        );
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let unit = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let bbox = Transform::rotate_y(45.0)
+            .then(&Transform::translate(Vec3::new(0.0, 5.0, 0.0)))
+            .bounding_box(&unit);
+        let r: f64 = 2.0_f64.sqrt();
+        assert_close(bbox.min, Vec3::new(-r, 4.0, -r));
+        assert_close(bbox.max, Vec3::new(r, 6.0, r));
+    }
+}
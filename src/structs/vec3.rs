@@ -176,7 +176,21 @@ impl ops::Neg for Vec3 {
     }
 }
 
+impl ops::Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        match i {
+            0 => &self.d0,
+            1 => &self.d1,
+            2 => &self.d2,
+            _ => panic!("Vec3 index out of range: {}", i),
+        }
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::unnecessary_cast)]
 mod test {
     use super::*;
 
@@ -418,4 +432,12 @@ mod test {
             -vec
         )
     }
+
+    #[test]
+    fn test_index() {
+        let vec = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(vec[0], 1.0);
+        assert_eq!(vec[1], 2.0);
+        assert_eq!(vec[2], 3.0);
+    }
 }
@@ -0,0 +1,134 @@
+use crate::structs::aabb::Aabb;
+use crate::structs::vec3::Vec3;
+
+/// Dense 3D grid of samples spanning `bounds`, with `channels` values per
+/// sample, e.g. one for density or three for an RGB emission.
+///
+/// Samples sit on the corners of the cells, so the first and last ones along
+/// each axis lie on the faces of `bounds`, and values in between are
+/// interpolated trilinearly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelGrid {
+    pub resolution: [usize; 3],
+    pub channels: usize,
+    pub bounds: Aabb,
+    /// Values with the channel varying fastest, then x, then y, then z.
+    data: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// Panics if `data` does not hold exactly one value per channel and sample.
+    pub fn new(resolution: [usize; 3], channels: usize, bounds: Aabb, data: Vec<f32>) -> VoxelGrid {
+        assert_eq!(
+            data.len(),
+            resolution[0] * resolution[1] * resolution[2] * channels,
+            "voxel data does not match the grid size"
+        );
+        VoxelGrid {
+            resolution,
+            channels,
+            bounds,
+            data,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize, channel: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        f64::from(self.data[((z * ny + y) * nx + x) * self.channels + channel])
+    }
+
+    /// Trilinearly interpolated value of `channel` at `p`, zero outside the bounds.
+    pub fn value(&self, p: &Vec3, channel: usize) -> f64 {
+        let mut cell: [usize; 3] = [0; 3];
+        let mut weight: [f64; 3] = [0.0; 3];
+        for a in 0..3 {
+            let extent: f64 = self.bounds.max[a] - self.bounds.min[a];
+            let n: usize = self.resolution[a];
+            let relative: f64 = if extent > 0.0 {
+                (p[a] - self.bounds.min[a]) / extent
+            } else {
+                0.0
+            };
+            // Written to also reject NaN.
+            if !(0.0..=1.0).contains(&relative) || n == 0 {
+                return 0.0;
+            }
+            let x: f64 = relative * (n - 1) as f64;
+            let i: usize = (x as usize).min(n.saturating_sub(2));
+            cell[a] = i;
+            weight[a] = if n > 1 { x - i as f64 } else { 0.0 };
+        }
+
+        let mut value: f64 = 0.0;
+        for corner in 0..8 {
+            let mut index: [usize; 3] = cell;
+            let mut w: f64 = 1.0;
+            for a in 0..3 {
+                if corner & (1 << a) != 0 {
+                    if weight[a] == 0.0 {
+                        w = 0.0;
+                        break;
+                    }
+                    index[a] += 1;
+                    w *= weight[a];
+                } else {
+                    w *= 1.0 - weight[a];
+                }
+            }
+            if w != 0.0 {
+                value += w * self.get(index[0], index[1], index[2], channel);
+            }
+        }
+        value
+    }
+
+    /// Largest value of `channel` anywhere in the grid, or zero if it is empty.
+    pub fn max_value(&self, channel: usize) -> f64 {
+        self.data
+            .iter()
+            .skip(channel)
+            .step_by(self.channels.max(1))
+            .fold(0.0f64, |max, &v| max.max(f64::from(v)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid() -> VoxelGrid {
+        // 3 x 2 x 2 samples over [0, 2] x [0, 1] x [0, 1], with channel 0 equal
+        // to x + 10 y + 100 z at each sample and channel 1 its negation.
+        let mut data: Vec<f32> = Vec::new();
+        for z in 0..2 {
+            for y in 0..2 {
+                for x in 0..3 {
+                    let v: f32 = (x + 10 * y + 100 * z) as f32;
+                    data.push(v);
+                    data.push(-v);
+                }
+            }
+        }
+        VoxelGrid::new(
+            [3, 2, 2],
+            2,
+            Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0)),
+            data,
+        )
+    }
+
+    #[test]
+    fn test_value() {
+        let grid = grid();
+        assert_eq!(grid.get(2, 1, 0, 0), 12.0);
+        assert_eq!(grid.value(&Vec3::new(2.0, 1.0, 1.0), 0), 112.0);
+        // A linear field is reproduced exactly.
+        let p = Vec3::new(1.5, 0.25, 0.75);
+        assert!((grid.value(&p, 0) - 79.0).abs() < 1e-9);
+        assert!((grid.value(&p, 1) + 79.0).abs() < 1e-9);
+        assert_eq!(grid.value(&Vec3::new(-0.1, 0.5, 0.5), 0), 0.0);
+        assert_eq!(grid.value(&Vec3::new(1.0, 0.5, 1.1), 0), 0.0);
+        assert_eq!(grid.max_value(0), 112.0);
+        assert_eq!(grid.max_value(1), 0.0);
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use super::Texture;
+use crate::structs::vec3::Vec3;
+
+/// 3D checkerboard of cubes `scale` wide, alternating between two textures.
+/// Being solid, it does not need surface coordinates.
+///
+/// A surface lying on a cell boundary flickers between the two sides from
+/// rounding, so shift the board with `offset` to put such a surface mid-cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checker {
+    pub scale: f64,
+    /// Corner of the cell at the origin of the board.
+    pub offset: Vec3,
+    pub even: Arc<Texture>,
+    pub odd: Arc<Texture>,
+}
+
+impl Checker {
+    pub fn new<E: Into<Texture>, O: Into<Texture>>(scale: f64, even: E, odd: O) -> Checker {
+        Checker {
+            scale,
+            offset: Vec3::new(0.0, 0.0, 0.0),
+            even: Arc::new(even.into()),
+            odd: Arc::new(odd.into()),
+        }
+    }
+
+    pub fn offset(mut self, offset: Vec3) -> Checker {
+        self.offset = offset;
+        self
+    }
+
+    pub fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        let cell = |c: f64| -> i64 { (c / self.scale).floor() as i64 };
+        let q: Vec3 = *p - self.offset;
+        if (cell(q.x()) + cell(q.y()) + cell(q.z())).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let black = Vec3::new(0.0, 0.0, 0.0);
+        let checker = Checker::new(0.5, white, black);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(0.1, 0.1, 0.1)), white);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(0.6, 0.1, 0.1)), black);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(0.6, 0.6, 0.1)), white);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(-0.1, 0.1, 0.1)), black);
+        assert_eq!(checker.value(0.0, 0.0, &Vec3::new(-0.6, 0.1, 0.1)), white);
+
+        // Checkers nest.
+        let nested = Checker::new(2.0, Texture::Checker(checker), black);
+        assert_eq!(nested.value(0.0, 0.0, &Vec3::new(0.6, 0.1, 0.1)), black);
+        assert_eq!(nested.value(0.0, 0.0, &Vec3::new(0.6, 0.6, 0.1)), white);
+        assert_eq!(nested.value(0.0, 0.0, &Vec3::new(2.1, 0.6, 0.1)), black);
+
+        // Half a cell up, both sides of y = 0 fall in the same cell.
+        let shifted = Checker::new(0.5, white, black).offset(Vec3::new(0.0, 0.25, 0.0));
+        assert_eq!(shifted.value(0.0, 0.0, &Vec3::new(0.1, 1e-12, 0.1)), black);
+        assert_eq!(shifted.value(0.0, 0.0, &Vec3::new(0.1, -1e-12, 0.1)), black);
+    }
+}
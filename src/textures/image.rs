@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::loaders::image::load_image;
+use crate::loaders::LoadError;
+use crate::render::framebuffer::Framebuffer;
+use crate::structs::vec3::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// What lookups outside `[0, 1]` read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrap {
+    Repeat,
+    /// Mirror the image on every repetition.
+    Mirror,
+    /// Repeat the edge texels.
+    Clamp,
+}
+
+impl Wrap {
+    fn apply(self, i: i64, n: usize) -> usize {
+        let n: i64 = n as i64;
+        let wrapped: i64 = match self {
+            Wrap::Repeat => i.rem_euclid(n),
+            Wrap::Mirror => {
+                let m: i64 = i.rem_euclid(2 * n);
+                if m < n {
+                    m
+                } else {
+                    2 * n - 1 - m
+                }
+            }
+            Wrap::Clamp => i.clamp(0, n - 1),
+        };
+        wrapped as usize
+    }
+}
+
+/// Linear RGB image mapped onto a surface by its (`u`, `v`) coordinates, with
+/// `v = 0` at the bottom row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTexture {
+    pub image: Arc<Framebuffer>,
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+impl ImageTexture {
+    pub fn new(image: Arc<Framebuffer>) -> ImageTexture {
+        ImageTexture {
+            image,
+            filter: Filter::Bilinear,
+            wrap: Wrap::Repeat,
+        }
+    }
+
+    /// Loads a PPM or PNG file, see `loaders::image::load_image`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ImageTexture, LoadError> {
+        Ok(ImageTexture::new(Arc::new(load_image(path)?)))
+    }
+
+    pub fn filter(mut self, filter: Filter) -> ImageTexture {
+        self.filter = filter;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: Wrap) -> ImageTexture {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn value(&self, u: f64, v: f64, _p: &Vec3) -> Vec3 {
+        let (width, height) = (self.image.width, self.image.height);
+        if width == 0 || height == 0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let x: f64 = u * width as f64;
+        let y: f64 = (1.0 - v) * height as f64;
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Texel centers sit at half-integer coordinates.
+                let (x, y): (f64, f64) = (x - 0.5, y - 0.5);
+                let (x0, y0): (f64, f64) = (x.floor(), y.floor());
+                let (fx, fy): (f64, f64) = (x - x0, y - y0);
+                let (x0, y0): (i64, i64) = (x0 as i64, y0 as i64);
+                let top: Vec3 = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+                let bottom: Vec3 =
+                    self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        self.image.get(
+            self.wrap.apply(x, self.image.width),
+            self.wrap.apply(y, self.image.height),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texture() -> ImageTexture {
+        let mut image = Framebuffer::new(2, 2);
+        image.set(0, 0, Vec3::new(1.0, 0.0, 0.0));
+        image.set(1, 0, Vec3::new(0.0, 1.0, 0.0));
+        image.set(0, 1, Vec3::new(0.0, 0.0, 1.0));
+        image.set(1, 1, Vec3::new(1.0, 1.0, 1.0));
+        ImageTexture::new(Arc::new(image))
+    }
+
+    #[test]
+    fn test_nearest() {
+        let tex = texture().filter(Filter::Nearest);
+        let p = Vec3::new(0.0, 0.0, 0.0);
+        // v = 1 is the top row.
+        assert_eq!(tex.value(0.25, 0.75, &p), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(tex.value(0.75, 0.75, &p), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(tex.value(0.25, 0.25, &p), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(tex.value(1.25, 0.75, &p), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            tex.clone().wrap(Wrap::Clamp).value(1.25, 0.75, &p),
+            Vec3::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            tex.wrap(Wrap::Mirror).value(1.25, 0.75, &p),
+            Vec3::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let tex = texture().wrap(Wrap::Clamp);
+        let p = Vec3::new(0.0, 0.0, 0.0);
+        assert_eq!(tex.value(0.25, 0.75, &p), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(tex.value(0.5, 0.5, &p), Vec3::new(0.5, 0.5, 0.5));
+        assert_eq!(tex.value(0.5, 0.75, &p), Vec3::new(0.5, 0.5, 0.0));
+        assert_eq!(tex.value(0.0, 0.75, &p), Vec3::new(1.0, 0.0, 0.0));
+
+        // Repeating blends the left edge with the right one.
+        let repeat = tex.wrap(Wrap::Repeat);
+        assert_eq!(repeat.value(0.0, 0.75, &p), Vec3::new(0.5, 0.5, 0.0));
+    }
+}
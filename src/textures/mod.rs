@@ -0,0 +1,40 @@
+use crate::structs::vec3::Vec3;
+
+pub mod checker;
+pub mod image;
+pub mod noise;
+pub mod worley;
+
+use self::checker::Checker;
+use self::image::ImageTexture;
+use self::noise::NoiseTexture;
+use self::worley::WorleyTexture;
+
+/// Color that varies over a surface, looked up by surface coordinates (`u`,
+/// `v`) and hit point `p`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Texture {
+    Solid(Vec3),
+    Checker(Checker),
+    Image(ImageTexture),
+    Noise(NoiseTexture),
+    Worley(WorleyTexture),
+}
+
+impl Texture {
+    pub fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
+        match self {
+            Texture::Solid(color) => *color,
+            Texture::Checker(ref texture) => texture.value(u, v, p),
+            Texture::Image(ref texture) => texture.value(u, v, p),
+            Texture::Noise(ref texture) => texture.value(u, v, p),
+            Texture::Worley(ref texture) => texture.value(u, v, p),
+        }
+    }
+}
+
+impl From<Vec3> for Texture {
+    fn from(color: Vec3) -> Texture {
+        Texture::Solid(color)
+    }
+}
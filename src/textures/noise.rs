@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::structs::sampler::Sampler;
+use crate::structs::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+/// How `Perlin` blends the gradients of the eight lattice points around a
+/// lookup.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Smoothing {
+    /// Plain trilinear weights; the noise has visible creases along the lattice.
+    Trilinear,
+    /// Weights eased with the Hermite cubic `3t^2 - 2t^3`, smooth across cells.
+    Hermite,
+}
+
+/// Gradient noise on an integer lattice, returning values in about `[-1, 1]`.
+///
+/// The gradients and permutations are drawn from a `Sampler`, so the same
+/// seed always gives the same noise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Perlin {
+    pub smoothing: Smoothing,
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut sampler = Sampler::new(seed);
+        let gradients: Vec<Vec3> = (0..POINT_COUNT)
+            .map(|_| loop {
+                let g: Vec3 = Vec3::new(
+                    sampler.gen_range(-1.0, 1.0),
+                    sampler.gen_range(-1.0, 1.0),
+                    sampler.gen_range(-1.0, 1.0),
+                );
+                let length_squared: f64 = g.length_squared();
+                if length_squared > 1e-6 && length_squared <= 1.0 {
+                    break Vec3::unit_vector(&g);
+                }
+            })
+            .collect();
+        let mut permutation = || -> Vec<usize> {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut sampler);
+            p
+        };
+        Perlin {
+            smoothing: Smoothing::Hermite,
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+            gradients,
+        }
+    }
+
+    pub fn smoothing(mut self, smoothing: Smoothing) -> Perlin {
+        self.smoothing = smoothing;
+        self
+    }
+
+    pub fn noise(&self, p: &Vec3) -> f64 {
+        let (i, j, k) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (u, v, w) = (p.x() - i, p.y() - j, p.z() - k);
+        let (i, j, k) = (i as i64, j as i64, k as i64);
+        let ease = |t: f64| -> f64 {
+            match self.smoothing {
+                Smoothing::Trilinear => t,
+                Smoothing::Hermite => t * t * (3.0 - 2.0 * t),
+            }
+        };
+        let (uu, vv, ww) = (ease(u), ease(v), ease(w));
+
+        let mut accum: f64 = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index: usize = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let offset: Vec3 = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * Vec3::dot(&self.gradients[index], &offset);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `octaves` layers of noise, each at twice the frequency and
+    /// `gain` times the amplitude of the previous one.
+    pub fn fbm(&self, p: &Vec3, octaves: usize, lacunarity: f64, gain: f64) -> f64 {
+        let mut accum: f64 = 0.0;
+        let mut point: Vec3 = *p;
+        let mut weight: f64 = 1.0;
+        for _ in 0..octaves {
+            accum += weight * self.noise(&point);
+            weight *= gain;
+            point *= lacunarity;
+        }
+        accum
+    }
+
+    /// Like `fbm` with the usual lacunarity 2 and gain 1/2, but summing the
+    /// absolute value of each octave. Always non-negative.
+    pub fn turbulence(&self, p: &Vec3, octaves: usize) -> f64 {
+        let mut accum: f64 = 0.0;
+        let mut point: Vec3 = *p;
+        let mut weight: f64 = 1.0;
+        for _ in 0..octaves {
+            accum += weight * self.noise(&point).abs();
+            weight *= 0.5;
+            point *= 2.0;
+        }
+        accum
+    }
+}
+
+/// Which function of the noise a `NoiseTexture` shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisePattern {
+    Noise,
+    Turbulence {
+        octaves: usize,
+    },
+    Fbm {
+        octaves: usize,
+        lacunarity: f64,
+        gain: f64,
+    },
+    /// Bands along z, distorted by turbulence.
+    Marble {
+        octaves: usize,
+        /// How strongly turbulence bends the bands.
+        distortion: f64,
+    },
+    /// Rings around the y axis, distorted by turbulence.
+    Wood {
+        octaves: usize,
+        distortion: f64,
+    },
+}
+
+/// Blends two colors by a noise pattern evaluated at the hit point, scaled by
+/// `scale`. The pattern value is mapped to `[0, 1]`, with 0 giving `low` and 1
+/// giving `high`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseTexture {
+    pub perlin: Arc<Perlin>,
+    pub pattern: NoisePattern,
+    pub scale: f64,
+    pub low: Vec3,
+    pub high: Vec3,
+}
+
+impl NoiseTexture {
+    pub fn new(perlin: Arc<Perlin>, pattern: NoisePattern, scale: f64) -> NoiseTexture {
+        NoiseTexture {
+            perlin,
+            pattern,
+            scale,
+            low: Vec3::new(0.0, 0.0, 0.0),
+            high: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn colors(mut self, low: Vec3, high: Vec3) -> NoiseTexture {
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    /// The pattern at `p`, in `[0, 1]`.
+    pub fn pattern_value(&self, p: &Vec3) -> f64 {
+        let p: Vec3 = *p * self.scale;
+        let t: f64 = match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.perlin.noise(&p)),
+            NoisePattern::Turbulence { octaves } => self.perlin.turbulence(&p, octaves),
+            NoisePattern::Fbm {
+                octaves,
+                lacunarity,
+                gain,
+            } => 0.5 * (1.0 + self.perlin.fbm(&p, octaves, lacunarity, gain)),
+            NoisePattern::Marble {
+                octaves,
+                distortion,
+            } => 0.5 * (1.0 + (p.z() + distortion * self.perlin.turbulence(&p, octaves)).sin()),
+            NoisePattern::Wood {
+                octaves,
+                distortion,
+            } => {
+                let radius: f64 = (p.x() * p.x() + p.z() * p.z()).sqrt();
+                let rings: f64 = radius + distortion * self.perlin.turbulence(&p, octaves);
+                rings - rings.floor()
+            }
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    pub fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        let t: f64 = self.pattern_value(p);
+        self.low * (1.0 - t) + self.high * t
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seeded() {
+        let p = Vec3::new(1.3, -2.7, 0.4);
+        assert_eq!(Perlin::new(3).noise(&p), Perlin::new(3).noise(&p));
+        assert_ne!(Perlin::new(3).noise(&p), Perlin::new(4).noise(&p));
+    }
+
+    #[test]
+    fn test_noise() {
+        let perlin = Perlin::new(0);
+        // Gradient noise vanishes on the lattice.
+        assert_eq!(perlin.noise(&Vec3::new(2.0, -3.0, 5.0)), 0.0);
+
+        let mut sampler = Sampler::new(1);
+        for _ in 0..1000 {
+            let p = Vec3::new(
+                sampler.gen_range(-50.0, 50.0),
+                sampler.gen_range(-50.0, 50.0),
+                sampler.gen_range(-50.0, 50.0),
+            );
+            let n: f64 = perlin.noise(&p);
+            assert!(n.abs() <= 1.0, "{}", n);
+            assert!(perlin.turbulence(&p, 7) >= 0.0);
+            // Hermite easing is continuous, so nearby points give close values.
+            let step = Vec3::new(1e-7, 1e-7, 1e-7);
+            assert!((perlin.noise(&(p + step)) - n).abs() < 1e-5);
+        }
+        // A single octave of fBm is the noise itself.
+        let p = Vec3::new(0.3, 0.6, 0.9);
+        assert_eq!(perlin.fbm(&p, 1, 2.0, 0.5), perlin.noise(&p));
+    }
+
+    #[test]
+    fn test_smoothing() {
+        // Both variants agree on the lattice and at cell centres, where the
+        // eased and linear weights are equal.
+        let hermite = Perlin::new(0);
+        let trilinear = Perlin::new(0).smoothing(Smoothing::Trilinear);
+        let center = Vec3::new(0.5, 1.5, 2.5);
+        assert!((hermite.noise(&center) - trilinear.noise(&center)).abs() < 1e-12);
+        let p = Vec3::new(0.2, 1.1, 2.3);
+        assert_ne!(hermite.noise(&p), trilinear.noise(&p));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let perlin = Arc::new(Perlin::new(0));
+        let patterns = [
+            NoisePattern::Noise,
+            NoisePattern::Turbulence { octaves: 7 },
+            NoisePattern::Fbm {
+                octaves: 5,
+                lacunarity: 2.0,
+                gain: 0.5,
+            },
+            NoisePattern::Marble {
+                octaves: 7,
+                distortion: 10.0,
+            },
+            NoisePattern::Wood {
+                octaves: 3,
+                distortion: 0.5,
+            },
+        ];
+        let low = Vec3::new(0.2, 0.1, 0.0);
+        let high = Vec3::new(0.8, 0.5, 0.3);
+        for pattern in patterns.iter() {
+            let texture = NoiseTexture::new(Arc::clone(&perlin), *pattern, 4.0).colors(low, high);
+            let mut sampler = Sampler::new(2);
+            for _ in 0..100 {
+                let p = Vec3::new(sampler.gen(), sampler.gen(), sampler.gen());
+                let t: f64 = texture.pattern_value(&p);
+                assert!((0.0..=1.0).contains(&t));
+                let color: Vec3 = texture.value(0.0, 0.0, &p);
+                assert!((color - (low * (1.0 - t) + high * t)).length() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::structs::sampler::splitmix64;
+use crate::structs::vec3::Vec3;
+
+/// Cellular noise: every unit cell of the lattice holds one feature point at a
+/// position hashed from the cell and the seed, and lookups measure the
+/// distance to the nearest of them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Worley {
+    pub seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Worley {
+        Worley { seed }
+    }
+
+    fn feature_point(&self, i: i64, j: i64, k: i64) -> Vec3 {
+        let mut hash: u64 = splitmix64(self.seed);
+        for c in [i, j, k].iter() {
+            hash = splitmix64(hash ^ *c as u64);
+        }
+        let unit = |bits: u64| -> f64 { (bits & 0x1f_ffff) as f64 / 0x20_0000 as f64 };
+        Vec3::new(
+            i as f64 + unit(hash),
+            j as f64 + unit(hash >> 21),
+            k as f64 + unit(hash >> 42),
+        )
+    }
+
+    /// Distances from `p` to the nearest and second-nearest feature points.
+    pub fn distances(&self, p: &Vec3) -> (f64, f64) {
+        let (i, j, k) = (
+            p.x().floor() as i64,
+            p.y().floor() as i64,
+            p.z().floor() as i64,
+        );
+        let mut f1: f64 = f64::INFINITY;
+        let mut f2: f64 = f64::INFINITY;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let d: f64 = (self.feature_point(i + di, j + dj, k + dk) - *p).length();
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+}
+
+/// Which distance a `WorleyTexture` shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellFeature {
+    /// Distance to the nearest point: round, cell-like blobs.
+    F1,
+    F2,
+    /// Difference of the two nearest distances: dark cracks along cell borders.
+    F2MinusF1,
+}
+
+/// Blends two colors by a Worley distance at the hit point, scaled by `scale`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorleyTexture {
+    pub worley: Worley,
+    pub feature: CellFeature,
+    pub scale: f64,
+    pub low: Vec3,
+    pub high: Vec3,
+}
+
+impl WorleyTexture {
+    pub fn new(worley: Worley, feature: CellFeature, scale: f64) -> WorleyTexture {
+        WorleyTexture {
+            worley,
+            feature,
+            scale,
+            low: Vec3::new(0.0, 0.0, 0.0),
+            high: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn colors(mut self, low: Vec3, high: Vec3) -> WorleyTexture {
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    /// The chosen distance at `p`, clamped to `[0, 1]`.
+    pub fn pattern_value(&self, p: &Vec3) -> f64 {
+        let (f1, f2) = self.worley.distances(&(*p * self.scale));
+        let t: f64 = match self.feature {
+            CellFeature::F1 => f1,
+            CellFeature::F2 => f2,
+            CellFeature::F2MinusF1 => f2 - f1,
+        };
+        t.clamp(0.0, 1.0)
+    }
+
+    pub fn value(&self, _u: f64, _v: f64, p: &Vec3) -> Vec3 {
+        let t: f64 = self.pattern_value(p);
+        self.low * (1.0 - t) + self.high * t
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::structs::sampler::Sampler;
+    use rand::Rng;
+
+    #[test]
+    fn test_distances() {
+        let worley = Worley::new(5);
+        let point: Vec3 = worley.feature_point(3, -2, 7);
+        assert_eq!(point, Worley::new(5).feature_point(3, -2, 7));
+        assert_ne!(point, Worley::new(6).feature_point(3, -2, 7));
+        assert!(point.x() >= 3.0 && point.x() < 4.0);
+        assert!(point.y() >= -2.0 && point.y() < -1.0);
+        assert_eq!(worley.distances(&point).0, 0.0);
+
+        let mut sampler = Sampler::new(0);
+        for _ in 0..200 {
+            let p = Vec3::new(
+                sampler.gen_range(-10.0, 10.0),
+                sampler.gen_range(-10.0, 10.0),
+                sampler.gen_range(-10.0, 10.0),
+            );
+            let (f1, f2) = worley.distances(&p);
+            assert!(f1 <= f2);
+            // The point of the cell containing p is never further than a diagonal.
+            assert!(f1 < 3.0_f64.sqrt());
+        }
+    }
+
+    #[test]
+    fn test_texture() {
+        let worley = Worley::new(1);
+        let point: Vec3 = worley.feature_point(0, 0, 0) * 0.5;
+        let texture = WorleyTexture::new(worley, CellFeature::F1, 2.0)
+            .colors(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(texture.value(0.0, 0.0, &point), Vec3::new(1.0, 0.0, 0.0));
+        let cracks = WorleyTexture::new(worley, CellFeature::F2MinusF1, 2.0);
+        assert!(cracks.pattern_value(&point) > 0.0);
+    }
+}